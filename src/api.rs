@@ -1,11 +1,284 @@
 use anyhow::{anyhow, Context, Result};
-use serde::Deserialize;
+use reqwest::blocking::{Client, ClientBuilder, RequestBuilder, Response};
+use reqwest::header::{
+    HeaderMap, AUTHORIZATION, COOKIE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+    REFERER, SET_COOKIE, USER_AGENT,
+};
+use serde::{Deserialize, Serialize};
 
 pub const BASE_URL: &str = "https://dmoj.ca";
+pub const DEFAULT_API_VERSION: &str = "v2";
+
+/// URLs of the judge's pages and API endpoints
+#[derive(Debug, Clone)]
+pub struct Endpoints {
+    base_url: String,
+    api_version: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            base_url: BASE_URL.to_string(),
+            api_version: DEFAULT_API_VERSION.to_string(),
+        }
+    }
+}
+
+impl Endpoints {
+    /// Use a different API version, e.g. `v1` for judges running an older DMOJ
+    pub fn api_version(mut self, api_version: impl Into<String>) -> Self {
+        self.api_version = api_version.into();
+        self
+    }
+
+    /// Use a different judge, e.g. `https://judge.example.com` for a mirror of DMOJ
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Base URL of the judge, e.g. `https://dmoj.ca`
+    pub fn judge_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn api(&self, path: &str) -> String {
+        format!("{}/api/{}/{}", self.base_url, self.api_version, path)
+    }
+
+    pub fn languages(&self) -> String {
+        self.api("languages")
+    }
+
+    pub fn problem(&self, problem: &str) -> String {
+        self.api(&format!("problem/{}", problem))
+    }
+
+    pub fn problems(&self) -> String {
+        self.api("problems")
+    }
+
+    pub fn submissions(&self) -> String {
+        self.api("submissions")
+    }
+
+    pub fn submission(&self, submission_id: &str) -> String {
+        self.api(&format!("submission/{}", submission_id))
+    }
+
+    /// Page of `problem` on the website
+    pub fn problem_page(&self, problem: &str) -> String {
+        format!("{}/problem/{}", self.base_url, problem)
+    }
+
+    /// Page that submissions to `problem` are posted to
+    pub fn submit_page(&self, problem: &str) -> String {
+        format!("{}/problem/{}/submit", self.base_url, problem)
+    }
+
+    /// Page showing a submission on the website
+    pub fn submission_page(&self, submission_id: &str) -> String {
+        format!("{}/submission/{}", self.base_url, submission_id)
+    }
+
+    /// Page that aborting a submission is posted to
+    pub fn abort(&self, submission_id: &str) -> String {
+        format!("{}/submission/{}/abort", self.base_url, submission_id)
+    }
+}
+
+/// How requests are authenticated
+#[derive(Debug, Clone)]
+pub enum Auth {
+    /// DMOJ API token, sent as a bearer token
+    Token(String),
+    /// Value of the `sessionid` cookie of a logged in browser session, for judges that don't
+    /// support API tokens
+    SessionCookie(String),
+}
+
+impl Auth {
+    pub fn apply(&self, request: RequestBuilder) -> RequestBuilder {
+        match self {
+            Auth::Token(token) => request.header(AUTHORIZATION, format!("Bearer {}", token)),
+            Auth::SessionCookie(cookie) => request.header(COOKIE, format!("sessionid={}", cookie)),
+        }
+    }
+
+    /// Short description for logging that doesn't reveal the secret
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Auth::Token(_) => "API token",
+            Auth::SessionCookie(_) => "session cookie",
+        }
+    }
+}
+
+/// Settings shared by every HTTP client we create
+#[derive(Default, Debug, Clone)]
+pub struct ClientOptions {
+    /// Proxy to send all requests through. If unset, reqwest falls back to the
+    /// `HTTPS_PROXY`/`HTTP_PROXY` environment variables.
+    pub proxy: Option<String>,
+    /// Skip TLS certificate verification. Only meant for local test judges.
+    pub insecure: bool,
+    /// Extra trusted root certificates in PEM format, e.g. of a TLS-inspecting proxy
+    pub ca_cert: Option<Vec<u8>>,
+    /// Read the languages from this file instead of fetching them, e.g. for offline testing
+    pub languages_file: Option<std::path::PathBuf>,
+    pub endpoints: Endpoints,
+}
+
+impl ClientOptions {
+    pub fn builder(&self) -> Result<ClientBuilder> {
+        let mut builder = Client::builder();
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(
+                reqwest::Proxy::all(proxy).with_context(|| format!("invalid proxy `{}`", proxy))?,
+            );
+        }
+        if self.insecure {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+        if let Some(pem) = &self.ca_cert {
+            let certs = reqwest::Certificate::from_pem_bundle(pem)
+                .with_context(|| "invalid CA certificate")?;
+            for cert in certs {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        Ok(builder)
+    }
+
+    /// Read the PEM certificates in `path` for `ca_cert`, making sure they can be used
+    pub fn load_ca_cert(path: &std::path::Path) -> Result<Vec<u8>> {
+        let pem = std::fs::read(path)
+            .with_context(|| format!("could not read CA certificate {}", path.display()))?;
+        let certs = reqwest::Certificate::from_pem_bundle(&pem)
+            .with_context(|| format!("{} is not a valid PEM certificate", path.display()))?;
+        if certs.is_empty() {
+            return Err(anyhow!(
+                "{} does not contain any PEM certificates",
+                path.display()
+            ));
+        }
+        Ok(pem)
+    }
+
+    pub fn client(&self) -> Result<Client> {
+        self.builder()?
+            .build()
+            .with_context(|| "could not build HTTP client")
+    }
+
+    /// Send `request`, explaining connection failures when a proxy is in use
+    pub fn send(&self, request: RequestBuilder) -> Result<Response> {
+        request.send().map_err(|err| {
+            let proxy = self.proxy.clone().or_else(|| {
+                ["HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy"]
+                    .into_iter()
+                    .find_map(|var| std::env::var(var).ok())
+            });
+            match proxy {
+                Some(proxy) if err.is_connect() => {
+                    anyhow!(err).context(format!("could not connect through proxy `{}`", proxy))
+                }
+                _ => match network_error_hint(&err) {
+                    Some(hint) => anyhow!(err).context(hint),
+                    None => anyhow!(err).context("API request failed"),
+                },
+            }
+        })
+    }
+}
+
+/// Explain a failed request in plain words, with what to try next, for the common ways of not
+/// reaching the judge at all. The original error is kept as the cause.
+fn network_error_hint(err: &reqwest::Error) -> Option<String> {
+    let host = err
+        .url()
+        .and_then(|url| url.host_str())
+        .unwrap_or("the judge")
+        .to_string();
+    if err.is_timeout() {
+        return Some(format!(
+            "{} did not respond in time; the judge may be busy or unreachable, try again later",
+            host
+        ));
+    }
+    if !err.is_connect() {
+        return None;
+    }
+    // reqwest doesn't tell connection failures apart, but its underlying errors do
+    let causes = std::iter::successors(std::error::Error::source(err), |err| err.source())
+        .map(|err| err.to_string().to_lowercase())
+        .collect::<Vec<_>>()
+        .join(": ");
+    let hint = if ["dns", "lookup", "name or service not known"]
+        .iter()
+        .any(|cause| causes.contains(cause))
+    {
+        format!(
+            "could not find {}; check your internet connection and that --judge-url is correct",
+            host
+        )
+    } else if ["certificate", "tls", "ssl", "handshake"]
+        .iter()
+        .any(|cause| causes.contains(cause))
+    {
+        format!(
+            "could not establish a secure connection to {}; behind a TLS-inspecting proxy or for a judge with its own certificate authority, pass its certificate with --cacert (or use --insecure for local test judges only)",
+            host
+        )
+    } else if causes.contains("refused") {
+        format!(
+            "{} refused the connection; check that --judge-url is correct and that the judge is running",
+            host
+        )
+    } else {
+        format!(
+            "could not connect to {}; check your internet connection and try again",
+            host
+        )
+    };
+    Some(hint)
+}
+
+/// Cache validator from a previous response, used to make conditional requests so unchanged
+/// resources aren't downloaded again
+#[derive(Debug, Clone)]
+pub enum Validator {
+    ETag(String),
+    LastModified(String),
+}
+
+impl Validator {
+    /// Validator sent by the server, if it supports conditional requests. ETags are preferred.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        header(ETAG)
+            .map(Validator::ETag)
+            .or_else(|| header(LAST_MODIFIED).map(Validator::LastModified))
+    }
+
+    /// Make `request` conditional on the resource having changed
+    pub fn apply(&self, request: RequestBuilder) -> RequestBuilder {
+        match self {
+            Validator::ETag(etag) => request.header(IF_NONE_MATCH, etag),
+            Validator::LastModified(date) => request.header(IF_MODIFIED_SINCE, date),
+        }
+    }
+}
 
 #[allow(dead_code)]
 /// DMOJ API response
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct APIResponse<T> {
     pub api_version: String,
     pub method: String,
@@ -16,14 +289,14 @@ pub struct APIResponse<T> {
 
 #[allow(dead_code)]
 /// DMOJ API data format for a single object
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct APISingleData<T> {
     pub object: T,
 }
 
 #[allow(dead_code)]
 /// DMOJ API data format for lists of objects
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct APIListData<T> {
     pub current_object_count: i32,
     pub objects_per_page: i32,
@@ -36,7 +309,7 @@ pub struct APIListData<T> {
 
 #[allow(dead_code)]
 /// DMOJ API error format
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct APIErrorFormat {
     pub code: i32,
     pub message: String,
@@ -44,7 +317,7 @@ pub struct APIErrorFormat {
 
 #[allow(dead_code)]
 /// DMOJ API /api/v2/languages format
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct APILanguage {
     pub id: i32,
     pub key: String,
@@ -56,8 +329,30 @@ pub struct APILanguage {
 }
 
 #[allow(dead_code)]
-/// DMOJ API /api/v2/submission/<submission id> format
+/// DMOJ API /api/v2/problem/<problem code> format
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct APIProblem {
+    pub code: String,
+    pub name: String,
+    pub time_limit: f64,
+    pub memory_limit: f64,
+    pub points: f64,
+    pub partial: bool,
+    /// Language keys that may be used to submit to this problem
+    pub languages: Vec<String>,
+}
+
+#[allow(dead_code)]
+/// DMOJ API /api/v2/problems format
 #[derive(Deserialize, Debug)]
+pub struct APIProblemSummary {
+    pub code: String,
+    pub name: String,
+}
+
+#[allow(dead_code)]
+/// DMOJ API /api/v2/submission/<submission id> format
+#[derive(Serialize, Deserialize, Debug)]
 pub struct APISubmission {
     pub id: i32,
     pub problem: String,
@@ -72,10 +367,54 @@ pub struct APISubmission {
     pub case_points: f64,
     pub case_total: f64,
     pub cases: Vec<APISubmissionCaseOrBatch>,
+    /// Whether only the pretests were run, e.g. during contests that grade pretests first.
+    /// Not reported by every judge.
+    #[serde(default)]
+    pub is_pretested: bool,
+}
+
+/// Top-level submission statuses after which the judge no longer changes the submission. Error
+/// statuses such as `CE` end polling right away, even if `result` isn't set yet.
+const TERMINAL_STATUSES: [&str; 4] = ["D", "IE", "CE", "AB"];
+
+impl APISubmission {
+    /// Whether grading is over. Some judge versions set `result` slightly before or after
+    /// `status` reaches a terminal state, so either counts.
+    pub fn is_finished(&self) -> bool {
+        self.result.is_some() || TERMINAL_STATUSES.contains(&self.status.as_str())
+    }
+
+    /// Longest time taken by a single case
+    pub fn max_case_time(&self) -> Option<f64> {
+        self.cases
+            .iter()
+            .flat_map(|item| match item {
+                APISubmissionCaseOrBatch::Case(case) => vec![case.time],
+                APISubmissionCaseOrBatch::Batch(batch) => {
+                    batch.cases.iter().map(|case| case.time).collect()
+                }
+            })
+            .reduce(f64::max)
+    }
 }
 
 #[allow(dead_code)]
-#[derive(Deserialize, Debug)]
+/// DMOJ API /api/v2/submissions format
+#[derive(Serialize, Deserialize, Debug)]
+pub struct APISubmissionSummary {
+    pub id: i32,
+    pub problem: String,
+    pub user: String,
+    pub date: String,
+    pub time: Option<f64>,
+    pub memory: Option<f64>,
+    pub points: Option<f64>,
+    pub language: String,
+    pub result: Option<String>,
+}
+
+#[allow(dead_code)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum APISubmissionCaseOrBatch {
     Case(APISubmissionCase),
@@ -83,7 +422,7 @@ pub enum APISubmissionCaseOrBatch {
 }
 
 #[allow(dead_code)]
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct APISubmissionCase {
     pub r#type: String,
     pub case_id: i32,
@@ -92,10 +431,25 @@ pub struct APISubmissionCase {
     pub memory: f64,
     pub points: f64,
     pub total: f64,
+    /// Name of the judge server that ran the case, only reported by some judges
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub judge: Option<String>,
+    /// Short checker message, if the judge shows feedback
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feedback: Option<String>,
+    /// Longer checker output, if the judge shows feedback
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extended_feedback: Option<String>,
+    /// Expected output, only shown by judges that reveal it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_output: Option<String>,
+    /// Output of the submission, only shown by judges that reveal it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
 }
 
 #[allow(dead_code)]
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct APISubmissionBatch {
     pub r#type: String,
     pub batch_id: i32,
@@ -104,12 +458,29 @@ pub struct APISubmissionBatch {
     pub total: f64,
 }
 
-pub fn get_languages() -> Result<Vec<APILanguage>> {
-    let json: APIResponse<APIListData<APILanguage>> =
-        reqwest::blocking::get(format!("{}/api/v2/languages", BASE_URL))
-            .with_context(|| "API request failed")?
-            .json()
-            .with_context(|| "converting API response to json failed")?;
+/// Call `f` up to `attempts` times, waiting a bit longer after each failure
+pub fn with_retries<T>(attempts: u32, mut f: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut attempt = 1;
+    loop {
+        match f() {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt < attempts => {
+                log::warn!(
+                    "Attempt {}/{} failed, retrying: {:#}",
+                    attempt,
+                    attempts,
+                    err
+                );
+                std::thread::sleep(std::time::Duration::from_secs(attempt.into()));
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Extract the data from a DMOJ API response, turning API errors into `Err`s
+pub fn unwrap_response<T>(json: APIResponse<T>) -> Result<T> {
     if let Some(error) = json.error {
         Err(anyhow!(
             "API request failed with code {} and message `{}`",
@@ -117,16 +488,288 @@ pub fn get_languages() -> Result<Vec<APILanguage>> {
             error.message
         ))
     } else if let Some(data) = json.data {
-        if data.has_more {
-            // TODO: fix this
-            log::error!(
-                "There is more than one page of languages, but we are only reading the first one"
-            );
-        }
-        Ok(data.objects)
+        Ok(data)
     } else {
         Err(anyhow!(
             "Neither data nor error were defined in the API response"
         ))
     }
 }
+
+pub fn get_languages(client_options: &ClientOptions) -> Result<Vec<APILanguage>> {
+    if let Some(path) = &client_options.languages_file {
+        return read_languages(path);
+    }
+    let client = client_options.client()?;
+    let json: APIResponse<APIListData<APILanguage>> = client_options
+        .send(client.get(client_options.endpoints.languages()))?
+        .json()
+        .with_context(|| "converting API response to json failed")?;
+    let data = unwrap_response(json)?;
+    if data.has_more {
+        // TODO: fix this
+        log::error!(
+            "There is more than one page of languages, but we are only reading the first one"
+        );
+    }
+    Ok(data.objects)
+}
+
+/// Read languages saved from the languages endpoint, either the whole API response or just its
+/// `data`
+fn read_languages(path: &std::path::Path) -> Result<Vec<APILanguage>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("could not read languages file {}", path.display()))?;
+    let data = match serde_json::from_str::<APIResponse<APIListData<APILanguage>>>(&contents) {
+        Ok(response) => unwrap_response(response)?,
+        Err(_) => serde_json::from_str::<APIListData<APILanguage>>(&contents)
+            .with_context(|| format!("could not parse languages file {}", path.display()))?,
+    };
+    log::info!(
+        "Read {} languages from {}",
+        data.objects.len(),
+        path.display()
+    );
+    Ok(data.objects)
+}
+
+/// crates.io API /api/v1/crates/<crate name> format, only the parts we need
+#[derive(Deserialize, Debug)]
+struct CratesIoResponse {
+    #[serde(rename = "crate")]
+    krate: CratesIoCrate,
+}
+
+#[derive(Deserialize, Debug)]
+struct CratesIoCrate {
+    max_stable_version: Option<String>,
+    max_version: String,
+}
+
+/// Latest released version of dmoj-submit on crates.io
+pub fn get_latest_version(client_options: &ClientOptions) -> Result<String> {
+    let client = client_options.client()?;
+    let url = format!("https://crates.io/api/v1/crates/{}", env!("CARGO_PKG_NAME"));
+    // crates.io rejects requests without a user agent
+    let request = client.get(url).header(
+        USER_AGENT,
+        concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")),
+    );
+    let json: CratesIoResponse = client_options
+        .send(request)?
+        .error_for_status()
+        .with_context(|| "crates.io request failed")?
+        .json()
+        .with_context(|| "converting crates.io response to json failed")?;
+    Ok(json
+        .krate
+        .max_stable_version
+        .unwrap_or(json.krate.max_version))
+}
+
+pub fn get_problem(
+    client_options: &ClientOptions,
+    problem: &str,
+    auth: &Auth,
+) -> Result<APIProblem> {
+    let client = client_options.client()?;
+    let json: APIResponse<APISingleData<APIProblem>> = client_options
+        .send(auth.apply(client.get(client_options.endpoints.problem(problem))))?
+        .json()
+        .with_context(|| "converting API response to json failed")?;
+    Ok(unwrap_response(json)?.object)
+}
+
+/// Search for problems whose name or code contains `query`
+pub fn search_problems(
+    client_options: &ClientOptions,
+    query: &str,
+) -> Result<Vec<APIProblemSummary>> {
+    let client = client_options.client()?;
+    let json: APIResponse<APIListData<APIProblemSummary>> = client_options
+        .send(
+            client
+                .get(client_options.endpoints.problems())
+                .query(&[("search", query)]),
+        )?
+        .json()
+        .with_context(|| "converting API response to json failed")?;
+    Ok(unwrap_response(json)?.objects)
+}
+
+/// Download source code to submit from `url`
+pub fn fetch_source(client_options: &ClientOptions, url: &str) -> Result<String> {
+    let client = client_options.client()?;
+    let response = client_options.send(client.get(url))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(anyhow!(
+            "fetching source from {} failed with {}",
+            url,
+            status
+        ));
+    }
+    response
+        .text()
+        .with_context(|| format!("could not read source from {}", url))
+}
+
+pub fn get_submission(
+    client_options: &ClientOptions,
+    auth: &Auth,
+    submission_id: &str,
+) -> Result<APISubmission> {
+    let client = client_options.client()?;
+    let json: APIResponse<APISingleData<APISubmission>> = client_options
+        .send(auth.apply(client.get(client_options.endpoints.submission(submission_id))))?
+        .json()
+        .with_context(|| "converting API response to json failed")?;
+    Ok(unwrap_response(json)?.object)
+}
+
+/// Fetch one page of submissions matching `filters` (e.g. `[("user", "me")]`). Pages start from 1.
+pub fn get_submissions(
+    client_options: &ClientOptions,
+    auth: &Auth,
+    filters: &[(&str, &str)],
+    page: i32,
+) -> Result<APIListData<APISubmissionSummary>> {
+    let client = client_options.client()?;
+    let json: APIResponse<APIListData<APISubmissionSummary>> = client_options
+        .send(
+            auth.apply(
+                client
+                    .get(client_options.endpoints.submissions())
+                    .query(filters)
+                    .query(&[("page", page)]),
+            ),
+        )?
+        .json()
+        .with_context(|| "converting API response to json failed")?;
+    unwrap_response(json)
+}
+
+/// Find the id of the most recent submission by `user` to `problem`
+pub fn get_latest_submission_id(
+    client_options: &ClientOptions,
+    auth: &Auth,
+    user: &str,
+    problem: &str,
+) -> Result<Option<i32>> {
+    let filters = [("user", user), ("problem", problem)];
+    let first_page = get_submissions(client_options, auth, &filters, 1)?;
+    // submissions are listed from oldest to newest, so the latest one is on the last page
+    let last_page = if first_page.total_pages > 1 {
+        get_submissions(client_options, auth, &filters, first_page.total_pages)?
+    } else {
+        first_page
+    };
+    Ok(last_page.objects.iter().map(|sub| sub.id).max())
+}
+
+/// Print the method, URL, headers and body of `request` to stderr, with credentials redacted
+pub fn dump_request(request: &RequestBuilder) -> Result<()> {
+    let request = request
+        .try_clone()
+        .with_context(|| "request cannot be dumped")?
+        .build()
+        .with_context(|| "could not build request")?;
+    eprintln!("{} {}", request.method(), request.url());
+    for (name, value) in request.headers() {
+        let value = if name == AUTHORIZATION || name == COOKIE {
+            "<redacted>"
+        } else {
+            value.to_str().unwrap_or("<binary>")
+        };
+        eprintln!("{}: {}", name, value);
+    }
+    if let Some(body) = request.body().and_then(|body| body.as_bytes()) {
+        eprintln!();
+        eprintln!("{}", String::from_utf8_lossy(body));
+    }
+    Ok(())
+}
+
+/// Encode `fields` and a file upload as `multipart/form-data`, returning the content type and the
+/// body
+pub fn multipart_form(
+    fields: &[(&str, &str)],
+    file_field: &str,
+    file_name: &str,
+    data: &[u8],
+) -> (String, Vec<u8>) {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|time| time.subsec_nanos())
+        .unwrap_or_default();
+    let boundary = format!("dmoj-submit-{:08x}{:08x}", nanos, data.len());
+    let mut body = Vec::new();
+    for (name, value) in fields {
+        body.extend(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                boundary, name, value
+            )
+            .as_bytes(),
+        );
+    }
+    body.extend(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+            boundary, file_field, file_name
+        )
+        .as_bytes(),
+    );
+    body.extend(data);
+    body.extend(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    (format!("multipart/form-data; boundary={}", boundary), body)
+}
+
+/// Get a CSRF token by loading `url`, which is needed to submit forms when authenticating with a
+/// session cookie
+pub fn get_csrf_token(client_options: &ClientOptions, auth: &Auth, url: &str) -> Result<String> {
+    let client = client_options.client()?;
+    let response = client_options.send(auth.apply(client.get(url)))?;
+    response
+        .headers()
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .find_map(|cookie| {
+            let (name_value, _) = cookie.split_once(';').unwrap_or((cookie, ""));
+            name_value.strip_prefix("csrftoken=").map(str::to_string)
+        })
+        .with_context(|| format!("{} did not set a CSRF token", url))
+}
+
+/// Ask the judge to stop grading `submission_id`, like the abort button on the submission page
+pub fn abort_submission(
+    client_options: &ClientOptions,
+    client: &Client,
+    auth: &Auth,
+    submission_id: &str,
+) -> Result<()> {
+    let url = client_options.endpoints.abort(submission_id);
+    let request = match auth {
+        // like submitting, aborting with a session cookie needs a CSRF token
+        Auth::SessionCookie(cookie) => {
+            let page = client_options.endpoints.submission_page(submission_id);
+            let csrf_token = get_csrf_token(client_options, auth, &page)?;
+            client
+                .post(&url)
+                .header(
+                    COOKIE,
+                    format!("sessionid={}; csrftoken={}", cookie, csrf_token),
+                )
+                .header(REFERER, &page)
+                .form(&[("csrfmiddlewaretoken", csrf_token.as_str())])
+        }
+        Auth::Token(_) => auth.apply(client.post(&url)),
+    };
+    let response = client_options.send(request)?;
+    let status = response.status();
+    if !status.is_success() && !status.is_redirection() {
+        return Err(anyhow!("aborting failed with code {}", status.as_u16()));
+    }
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use anyhow::{anyhow, Context, Result};
+use std::{fs, path::Path};
+
+/// DOS date of 1980-01-01, the earliest date zip files can store. Entries don't get a real
+/// modification time so that archives of the same files are identical.
+const DOS_DATE: u16 = (1 << 5) | 1;
+/// Marks file names as UTF-8
+const UTF8_FLAG: u16 = 1 << 11;
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+/// Bundle `files` into an uncompressed zip archive. Files are stored under their file names, so
+/// names must be unique.
+pub fn zip_files(files: &[impl AsRef<Path>]) -> Result<Vec<u8>> {
+    let mut archive = Vec::new();
+    let mut central_directory = Vec::new();
+    let mut names = Vec::new();
+    for file in files {
+        let file = file.as_ref();
+        let name = file
+            .file_name()
+            .and_then(|name| name.to_str())
+            .with_context(|| format!("{} has no valid file name", file.display()))?
+            .to_string();
+        if names.contains(&name) {
+            return Err(anyhow!("more than one file is named {}", name));
+        }
+        let data = fs::read(file).with_context(|| format!("could not read {}", file.display()))?;
+        let size = u32::try_from(data.len())
+            .with_context(|| format!("{} is too large to archive", file.display()))?;
+        let offset = u32::try_from(archive.len()).with_context(|| "archive is too large")?;
+        let crc = crc32(&data);
+
+        // fields shared by the local header and the central directory entry, starting at
+        // "version needed to extract"
+        let mut common = Vec::new();
+        common.extend(20u16.to_le_bytes());
+        common.extend(UTF8_FLAG.to_le_bytes());
+        // stored, i.e. no compression
+        common.extend(0u16.to_le_bytes());
+        common.extend(0u16.to_le_bytes());
+        common.extend(DOS_DATE.to_le_bytes());
+        common.extend(crc.to_le_bytes());
+        common.extend(size.to_le_bytes());
+        common.extend(size.to_le_bytes());
+        common.extend((name.len() as u16).to_le_bytes());
+        // no extra field
+        common.extend(0u16.to_le_bytes());
+
+        archive.extend(0x0403_4b50u32.to_le_bytes());
+        archive.extend(&common);
+        archive.extend(name.as_bytes());
+        archive.extend(&data);
+
+        central_directory.extend(0x0201_4b50u32.to_le_bytes());
+        // version made by
+        central_directory.extend(20u16.to_le_bytes());
+        central_directory.extend(&common);
+        // comment length, disk number, internal and external attributes
+        central_directory.extend([0; 10]);
+        central_directory.extend(offset.to_le_bytes());
+        central_directory.extend(name.as_bytes());
+        names.push(name);
+    }
+    let entries = u16::try_from(names.len()).with_context(|| "too many files to archive")?;
+    let directory_offset = u32::try_from(archive.len()).with_context(|| "archive is too large")?;
+    let directory_size = central_directory.len() as u32;
+    archive.extend(central_directory);
+    archive.extend(0x0605_4b50u32.to_le_bytes());
+    // number of this disk and of the disk with the central directory
+    archive.extend([0; 4]);
+    archive.extend(entries.to_le_bytes());
+    archive.extend(entries.to_le_bytes());
+    archive.extend(directory_size.to_le_bytes());
+    archive.extend(directory_offset.to_le_bytes());
+    // comment length
+    archive.extend(0u16.to_le_bytes());
+    Ok(archive)
+}
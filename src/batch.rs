@@ -0,0 +1,203 @@
+use crate::api::APISubmission;
+use anyhow::{anyhow, Context, Result};
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// A list of files to submit, read from a TOML or JSON file
+#[derive(Deserialize, Debug)]
+pub struct Manifest {
+    #[serde(rename = "submission")]
+    pub entries: Vec<ManifestEntry>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ManifestEntry {
+    /// File to submit, relative to the manifest
+    pub file: PathBuf,
+    /// Problem code, determined like for a regular submission if unset
+    pub problem: Option<String>,
+    /// Language key, determined like for a regular submission if unset
+    pub language: Option<String>,
+}
+
+/// Load a manifest, making the paths of its entries relative to the current directory
+pub fn load_manifest(path: &Path) -> Result<Manifest> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("could not read manifest {}", path.display()))?;
+    let mut manifest: Manifest = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&contents)
+            .with_context(|| format!("could not parse manifest {}", path.display()))?
+    } else {
+        toml::from_str(&contents)
+            .with_context(|| format!("could not parse manifest {}", path.display()))?
+    };
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    for entry in manifest.entries.iter_mut() {
+        entry.file = base_dir.join(&entry.file);
+    }
+    Ok(manifest)
+}
+
+/// Call `f` on every entry using up to `jobs` threads, returning the results in manifest order.
+/// Each thread waits `delay` between its submissions so that the judge isn't flooded, and the
+/// threads start spread out over `delay`.
+pub fn run_parallel<T: Send>(
+    entries: &[ManifestEntry],
+    jobs: usize,
+    delay: Duration,
+    f: impl Fn(&ManifestEntry) -> T + Sync,
+) -> Vec<T> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..entries.len()).map(|_| None).collect::<Vec<_>>());
+    std::thread::scope(|scope| {
+        let threads = jobs.min(entries.len());
+        for thread in 0..threads {
+            let next = &next;
+            let results = &results;
+            let f = &f;
+            scope.spawn(move || {
+                // stagger the threads' first submissions like their later ones, instead of
+                // submitting `jobs` entries at once
+                std::thread::sleep(delay.mul_f64(thread as f64 / threads as f64));
+                let mut first = true;
+                loop {
+                    let idx = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(entry) = entries.get(idx) else {
+                        break;
+                    };
+                    if !first {
+                        std::thread::sleep(delay);
+                    }
+                    first = false;
+                    let result = f(entry);
+                    results.lock().unwrap_or_else(|err| err.into_inner())[idx] = Some(result);
+                }
+            });
+        }
+    });
+    results
+        .into_inner()
+        .unwrap_or_else(|err| err.into_inner())
+        .into_iter()
+        .map(|result| result.expect("every entry is processed"))
+        .collect()
+}
+
+/// Totals over all entries of a batch, a scorecard of e.g. a practice session
+#[derive(Serialize, Debug)]
+pub struct Aggregate {
+    /// Entries that were submitted and graded
+    pub graded: usize,
+    /// Entries that couldn't be submitted or graded
+    pub failed: usize,
+    pub accepted: usize,
+    /// Problem points earned by the graded entries
+    pub points: f64,
+    /// Average execution time of the graded entries that report one, in seconds
+    pub average_time: Option<f64>,
+    /// Problems of graded entries that didn't pass every case
+    pub not_full_marks: Vec<String>,
+}
+
+impl Aggregate {
+    pub fn new(results: &[(String, Result<APISubmission>)]) -> Self {
+        let graded = results
+            .iter()
+            .filter_map(|(_, result)| result.as_ref().ok())
+            .collect::<Vec<_>>();
+        let times = graded.iter().filter_map(|sub| sub.time).collect::<Vec<_>>();
+        Self {
+            graded: graded.len(),
+            failed: results.len() - graded.len(),
+            accepted: graded
+                .iter()
+                .filter(|sub| sub.result.as_deref() == Some("AC"))
+                .count(),
+            points: graded.iter().filter_map(|sub| sub.points).sum(),
+            average_time: (!times.is_empty())
+                .then(|| times.iter().sum::<f64>() / times.len() as f64),
+            not_full_marks: graded
+                .iter()
+                .filter(|sub| sub.case_points < sub.case_total)
+                .map(|sub| sub.problem.clone())
+                .collect(),
+        }
+    }
+
+    fn print(&self) {
+        println!();
+        println!(
+            "{} {}/{}",
+            style("Accepted:").bold(),
+            self.accepted,
+            self.graded
+        );
+        println!("{} {}", style("Points:").bold(), self.points);
+        if let Some(average_time) = self.average_time {
+            println!("{} {:.3}s", style("Average time:").bold(), average_time);
+        }
+        if !self.not_full_marks.is_empty() {
+            println!(
+                "{} {}",
+                style("Not full marks:").bold(),
+                self.not_full_marks.join(", ")
+            );
+        }
+    }
+}
+
+/// Print the outcome of every entry of a batch along with totals and error if any of them
+/// failed. With `json`, only the totals are printed, as a single line of JSON.
+pub fn finish(results: &[(String, Result<APISubmission>)], json: bool) -> Result<()> {
+    let aggregate = Aggregate::new(results);
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string(&aggregate).with_context(|| "could not serialize totals")?
+        );
+    } else {
+        print_summary(results);
+        aggregate.print();
+    }
+    if aggregate.failed > 0 {
+        return Err(anyhow!(
+            "{} of {} submissions failed",
+            aggregate.failed,
+            results.len()
+        ));
+    }
+    Ok(())
+}
+
+fn print_summary(results: &[(String, Result<APISubmission>)]) {
+    println!("{}", style("Summary").bold().underlined());
+    let width = results
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    for (name, result) in results {
+        match result {
+            Ok(sub) => println!(
+                "{:<width$}  {:<12} {} {:.0}/{:.0}",
+                name,
+                sub.problem,
+                sub.result.as_deref().unwrap_or(&sub.status),
+                sub.case_points,
+                sub.case_total,
+                width = width
+            ),
+            Err(err) => println!(
+                "{:<width$}  {} {:#}",
+                name,
+                style("failed:").red(),
+                err,
+                width = width
+            ),
+        }
+    }
+}
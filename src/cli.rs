@@ -1,25 +1,85 @@
-use clap::{Args, Parser, Subcommand};
-use clap_verbosity_flag::Verbosity;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap_verbosity_flag::{Verbosity, WarnLevel};
 
 #[derive(Parser)]
 #[command(author, version, about)]
 pub struct Cli {
     #[command(flatten)]
-    pub verbose: Verbosity,
+    pub verbose: Verbosity<WarnLevel>,
+    /// Proxy to send all requests through, e.g. `http://proxy.example.com:8080`
+    /// (defaults to the `HTTPS_PROXY` environment variable)
+    #[arg(long, global = true)]
+    pub proxy: Option<String>,
+    /// Don't verify TLS certificates. DANGEROUS, only use this for local judges with
+    /// self-signed certificates
+    #[arg(long, global = true)]
+    pub insecure: bool,
+    /// Also trust the root certificates in this PEM file, e.g. of a TLS-inspecting proxy
+    #[arg(long, global = true, value_name = "PATH", conflicts_with = "insecure")]
+    pub cacert: Option<std::path::PathBuf>,
+    /// Version of the judge's API, for judges that don't use the same API version as DMOJ
+    #[arg(
+        long,
+        global = true,
+        default_value = crate::api::DEFAULT_API_VERSION,
+        value_parser = parse_api_version
+    )]
+    pub api_version: String,
+    /// Read the judge's languages from this JSON file, saved from the languages API endpoint,
+    /// instead of fetching them
+    #[arg(long, global = true, value_name = "PATH")]
+    pub languages_from_file: Option<std::path::PathBuf>,
+    /// Ignore the configuration files, so all settings have to be given as flags
+    #[arg(long, global = true)]
+    pub no_config: bool,
+    /// Show dates in UTC instead of the local or configured timezone
+    #[arg(long, global = true)]
+    pub utc: bool,
     #[command(subcommand)]
     pub command: Commands,
 }
 
+/// Accept version path segments like `v2`
+fn parse_api_version(version: &str) -> Result<String, String> {
+    match version.strip_prefix('v') {
+        Some(number) if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) => {
+            Ok(version.to_string())
+        }
+        _ => Err("expected `v` followed by a number, e.g. `v2`".to_string()),
+    }
+}
+
+// parsed once at startup, so the size of `SubmitArgs` doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 pub enum Commands {
     /// Set default API token, language, etc.
     SetConfig(SetConfigArgs),
     /// Show configuration
-    GetConfig,
+    GetConfig(GetConfigArgs),
     /// Submit to a problem
     Submit(SubmitArgs),
     /// Get available languages from DMOJ and print as `common_name: language_key` pairs
-    ListLanguages,
+    ListLanguages(ListLanguagesArgs),
+    /// Submit two files to the same problem and compare their results
+    Compare(CompareArgs),
+    /// Create a starter file for a problem from the language's code template
+    Init(InitArgs),
+    /// Delete locally stored state, such as recent submissions and problem case counts
+    ClearCache,
+    /// List your past submissions
+    Submissions(SubmissionsArgs),
+    /// Submit source files in a directory whenever they are saved
+    WatchDir(WatchDirArgs),
+    /// Show a problem's limits, points, and allowed languages
+    ProblemInfo(ProblemInfoArgs),
+    /// Submit the source of a previous submission again
+    Resubmit(ResubmitArgs),
+    /// Show a submission saved with `submit --output-dir` as if it was being graded, without
+    /// contacting the judge
+    Replay(ReplayArgs),
+    /// Show the version of dmoj-submit
+    Version(VersionArgs),
 }
 
 #[derive(Args)]
@@ -31,19 +91,440 @@ pub struct SetConfigArgs {
     /// File extension -> language key mapping, e.g. `cpp:cpp20,py:pypy3,java:java8`
     #[arg(short, long)]
     pub language: Option<String>,
+    /// Problem code to submit to when `--problem` is not given, instead of using the file name.
+    /// Pass an empty string to unset it.
+    #[arg(long)]
+    pub default_problem: Option<String>,
+    /// Set DMOJ username, used when looking up submissions
+    #[arg(short, long)]
+    pub username: Option<String>,
+    /// Set how to authenticate with the judge
+    #[arg(long, value_enum)]
+    pub auth: Option<crate::config::AuthMethod>,
+    /// Set the `sessionid` cookie used for session cookie authentication
+    #[arg(long)]
+    pub session_cookie: Option<String>,
+    /// Restore the default configuration before applying any other options
+    #[arg(long)]
+    pub reset: bool,
+    /// Keep the API token when resetting the configuration
+    #[arg(long, requires = "reset")]
+    pub keep_token: bool,
+    /// Reset without asking for confirmation
+    #[arg(long, requires = "reset")]
+    pub force: bool,
+}
+
+#[derive(Args)]
+pub struct GetConfigArgs {
+    /// Show the problem, language and token that `submit` would use and where each comes from
+    #[arg(long)]
+    pub effective: bool,
+    /// File to determine the effective problem and language for
+    #[arg(requires = "effective")]
+    pub file: Option<std::path::PathBuf>,
 }
 
 #[derive(Args)]
 pub struct SubmitArgs {
     /// File to submit
-    pub file: std::path::PathBuf,
-    /// Problem code
+    #[arg(required_unless_present_any = ["from_url", "manifest", "latest", "files"])]
+    pub file: Option<std::path::PathBuf>,
+    /// Files to bundle into a zip archive with `--zip`, for multi-file problems.
+    /// The language must be given explicitly.
+    #[arg(
+        long,
+        num_args = 1..,
+        requires_all = ["zip", "language"],
+        conflicts_with_all = [
+            "file", "from_url", "manifest", "latest", "git_rev", "preprocess",
+            "strip_trailing", "guard_duplicates"
+        ]
+    )]
+    pub files: Vec<std::path::PathBuf>,
+    /// Submit `--files` as a zip archive. Only works on judges that accept file uploads for the
+    /// language.
+    #[arg(long, requires = "files")]
+    pub zip: bool,
+    /// Submit the most recently modified file in the current directory with a known extension
+    #[arg(long, conflicts_with_all = ["file", "from_url", "manifest"])]
+    pub latest: bool,
+    /// Fetch the source code to submit from a URL instead of a file.
+    /// The problem and language must be given explicitly.
+    #[arg(long, conflicts_with = "file", requires_all = ["problem", "language"])]
+    pub from_url: Option<String>,
+    /// Submit every entry of a TOML or JSON manifest, one after the other
+    #[arg(long, conflicts_with_all = ["file", "from_url", "problem", "language"])]
+    pub manifest: Option<std::path::PathBuf>,
+    /// Skip manifest entries that were already submitted by an earlier, interrupted run with
+    /// the same manifest
+    #[arg(long, requires = "manifest")]
+    pub resume: bool,
+    /// Kind of problem, which affects how the language is determined and how the result is
+    /// shown. Output-only problems are always submitted as `text`.
+    #[arg(long, value_enum, default_value_t = ProblemType::Standard, conflicts_with_all = ["manifest", "files"])]
+    pub problem_type: ProblemType,
+    /// Print the totals of a manifest run (accepted, points, average time, and problems without
+    /// full marks) as a single line of JSON instead of the summary table
+    #[arg(long, requires = "manifest")]
+    pub json: bool,
+    /// Number of manifest entries to submit and grade at the same time [default: 1]
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "manifest",
+        conflicts_with_all = ["file", "from_url", "latest"],
+        value_parser = clap::value_parser!(u16).range(1..)
+    )]
+    pub jobs: Option<u16>,
+    /// Problem code or URL, e.g. `aplusb` or `https://dmoj.ca/problem/aplusb`
+    #[arg(short, long)]
+    pub problem: Option<String>,
+    /// API token
+    #[arg(short, long)]
+    pub token: Option<String>,
+    /// Submission language, or `auto` to infer it from the file extension
+    #[arg(short, long)]
+    pub language: Option<String>,
+    /// Unit used when displaying memory usage
+    #[arg(long, value_enum, default_value_t = MemoryUnit::Mb)]
+    pub memory_unit: MemoryUnit,
+    /// Ask for confirmation before submitting a file that was already submitted to the
+    /// same problem within the last minute
+    #[arg(long)]
+    pub guard_duplicates: bool,
+    /// Custom template for the final summary. Available placeholders are `{problem}`,
+    /// `{verdict}`, `{points}`, `{total}`, `{percentage}`, `{time}` and `{memory}`
+    #[arg(long)]
+    pub format: Option<String>,
+    /// Directory to save the graded submission's API response to, as `<submission id>.json`
+    #[arg(long)]
+    pub output_dir: Option<std::path::PathBuf>,
+    /// Warn if grading hasn't started after this many seconds in the judge queue
+    #[arg(long, value_name = "SECS", default_value_t = 30)]
+    pub wait_for_queue: u64,
+    /// Show a desktop notification when grading finishes
+    #[arg(long)]
+    pub notify: bool,
+    /// POST the graded submission as JSON to this URL when grading finishes, e.g. for a class
+    /// dashboard
+    #[arg(long, value_name = "URL")]
+    pub webhook: Option<reqwest::Url>,
+    /// Find the submission id by looking up your latest submission through the API instead of
+    /// relying on the submit redirect (requires a username to be configured)
+    #[arg(long)]
+    pub id_via_api: bool,
+    /// Run the source through the preprocessing command configured for its language
+    #[arg(long)]
+    pub preprocess: bool,
+    /// Point out the first case that wasn't accepted once grading finishes
+    #[arg(long)]
+    pub first_failure: bool,
+    /// Abort the submission on the judge as soon as a case isn't accepted, to save grading time
+    /// on problems with many cases. The submission then ends up as aborted (AB).
+    #[arg(long)]
+    pub abort_on_fail: bool,
+    /// Show grading in a full-screen view with the score so far and the elapsed time
+    #[arg(long, conflicts_with = "jobs")]
+    pub tui: bool,
+    /// Exit with an error unless the submission gets this verdict, e.g. `AC`
+    #[arg(long, value_name = "VERDICT")]
+    pub assert_verdict: Option<String>,
+    /// Exit with an error unless the submission scores at least this many points, i.e. the
+    /// first number of the final score, not its percentage (e.g. 10 for a full score of 10/10)
+    #[arg(long, value_name = "POINTS")]
+    pub assert_score: Option<f64>,
+    /// Exit with an error if any case takes longer than this many seconds, even if accepted
+    #[arg(long, value_name = "SECONDS")]
+    pub max_time: Option<f64>,
+    /// Exit with an error if the submission uses more than this many MB of memory, even if
+    /// accepted
+    #[arg(long, value_name = "MB")]
+    pub max_memory: Option<f64>,
+    /// Print the submission request (with credentials redacted) before sending it. With
+    /// `--dry-run`, the request is printed without being sent.
+    #[arg(long)]
+    pub dump_request: bool,
+    /// Show cases as a single updating line of verdict glyphs, only printing failed cases in full
+    #[arg(long, conflicts_with = "tui")]
+    pub compact_cases: bool,
+    /// Show each batch as its own line of cases filling in with its points, to see at a glance
+    /// which batch is failing. Cases are printed as usual when the output isn't a terminal.
+    #[arg(long, conflicts_with_all = ["tui", "compact_cases", "pager", "jobs"])]
+    pub batch_bars: bool,
+    /// Show the judge server that ran each case, if the judge reports it, e.g. to check whether
+    /// inconsistent TLEs come from one server
+    #[arg(long)]
+    pub show_judge: bool,
+    /// Show the checker's feedback below failed cases, with a diff of the expected and actual
+    /// output if the judge reveals them
+    #[arg(long)]
+    pub show_feedback: bool,
+    /// Remove comments before submitting, e.g. to get a solution with a large commented-out
+    /// library below the size limit. Supports C, C++, Java, and Python.
+    #[arg(long, conflicts_with_all = ["manifest", "files"])]
+    pub strip_comments: bool,
+    /// Remove trailing whitespace from every line before submitting, e.g. for answer
+    /// submissions in the `text` language
+    #[arg(long)]
+    pub strip_trailing: bool,
+    /// Submit the file as it was at this git revision instead of the working tree
+    #[arg(long, value_name = "REV", conflicts_with_all = ["from_url", "manifest"])]
+    pub git_rev: Option<String>,
+    /// Number of redirects to follow after submitting while looking for the submission page
+    #[arg(long, value_name = "N", default_value_t = 5)]
+    pub max_redirects: usize,
+    /// Show all cases in a pager (`$PAGER`, or `less -R`) once grading finishes instead of
+    /// printing them as they arrive
+    #[arg(long, conflicts_with_all = ["tui", "compact_cases", "jobs"])]
+    pub pager: bool,
+    /// Redraw the case list at most once every MS milliseconds, printing cases that arrive in
+    /// between together. By default, cases are printed as soon as they arrive.
+    #[arg(long, value_name = "MS")]
+    pub min_poll_changes: Option<u64>,
+    /// Write progress events (submission, each case and its changes, and the final result) as
+    /// JSON lines to this already open file descriptor, e.g. `--status-fd 3 3>events.jsonl`
+    #[arg(long, value_name = "FD")]
+    pub status_fd: Option<u32>,
+    /// Once grading finishes, print how long fetching the languages, submitting, waiting in the
+    /// queue, and grading took, to tell where slowness comes from
+    #[arg(long, conflicts_with = "jobs")]
+    pub timings: bool,
+    /// Leave a final "Graded in" line when grading finishes instead of clearing the spinner,
+    /// e.g. to keep a trace in CI logs
+    #[arg(long)]
+    pub persist_progress: bool,
+    /// Submit to the judge at this base URL, e.g. `https://dmoj.ca`. Give it more than once to
+    /// submit to several judges, e.g. a mirror, and compare their verdicts. Tokens for judges
+    /// other than DMOJ come from the `judge_tokens` table of the configuration.
+    #[arg(long, value_name = "URL", conflicts_with_all = ["manifest", "files"])]
+    pub judge_url: Vec<String>,
+    /// Wait this many seconds before submitting, e.g. until a contest problem unlocks
+    #[arg(long, value_name = "SECS", conflicts_with = "at")]
+    pub delay_start: Option<u64>,
+    /// Wait until this time of day (`HH:MM` or `HH:MM:SS`, local time) before submitting
+    #[arg(long, value_name = "TIME")]
+    pub at: Option<String>,
+    /// Run this shell command first, e.g. a local test suite, and only submit if it succeeds
+    #[arg(long, value_name = "CMD")]
+    pub require_local_pass: Option<String>,
+    /// Print the SHA-256 of the source as it is submitted, i.e. after preprocessing and
+    /// stripping trailing whitespace
+    #[arg(long, conflicts_with_all = ["manifest", "files"])]
+    pub print_hash: bool,
+    /// Determine the problem, language, and source and print them along with the source's
+    /// SHA-256, but don't submit
+    #[arg(long, conflicts_with_all = ["manifest", "files"])]
+    pub dry_run: bool,
+    /// Print each change of the submission's top-level status (e.g. `QU -> G -> D`) with the
+    /// time since polling started, to tell queue delays from grading stalls
+    #[arg(long)]
+    pub raw_status: bool,
+    /// Show the problem, language, and file and ask for confirmation before submitting, as if
+    /// `confirm_before_submit` was set in the configuration
+    #[arg(long, conflicts_with = "yes")]
+    pub confirm: bool,
+    /// Submit without asking for confirmation, even if `confirm_before_submit` is set
+    #[arg(short, long)]
+    pub yes: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProblemType {
+    /// The source is run on each case
+    #[default]
+    Standard,
+    /// The output itself is submitted instead of a program
+    OutputOnly,
+    /// The program talks to an interactor while running
+    Interactive,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum MemoryUnit {
+    /// Kilobytes
+    Kb,
+    /// Megabytes
+    Mb,
+    /// Pick the unit based on the amount of memory used
+    Auto,
+}
+
+/// How informational commands print their results
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Styled text
+    Human,
+    /// The data as returned by the API, as JSON
+    Json,
+}
+
+#[derive(Args)]
+pub struct ListLanguagesArgs {
+    /// Field to sort languages by
+    #[arg(long, value_enum, default_value_t = LanguageSortKey::Name)]
+    pub sort_by: LanguageSortKey,
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+    pub output: OutputFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum LanguageSortKey {
+    /// Common name
+    Name,
+    /// Language key
+    Key,
+    /// Language id
+    Id,
+}
+
+#[derive(Args)]
+pub struct CompareArgs {
+    /// First file to submit
+    pub file_a: std::path::PathBuf,
+    /// Second file to submit
+    pub file_b: std::path::PathBuf,
+    /// Problem code or URL
+    #[arg(short, long)]
+    pub problem: Option<String>,
+    /// API token
+    #[arg(short, long)]
+    pub token: Option<String>,
+    /// Submission language, used for both files, or `auto` to infer it from each file's
+    /// extension
+    #[arg(short, long)]
+    pub language: Option<String>,
+}
+
+#[derive(Args)]
+pub struct InitArgs {
+    /// Problem code, used as the file name
+    pub problem: String,
+    /// Language key, whose code template is used and whose extension is used for the file
+    #[arg(short, long)]
+    pub language: String,
+    /// File extension to use, e.g. for languages that no extension is mapped to
+    #[arg(long)]
+    pub ext: Option<String>,
+    /// Overwrite the file if it already exists
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args)]
+pub struct SubmissionsArgs {
+    /// Only show submissions to this problem
     #[arg(short, long)]
     pub problem: Option<String>,
+    /// User whose submissions to show, defaults to the configured username
+    #[arg(short, long)]
+    pub user: Option<String>,
     /// API token
     #[arg(short, long)]
     pub token: Option<String>,
-    /// Submission language
+    /// Only show submissions with this verdict, e.g. `WA`
+    #[arg(long)]
+    pub verdict: Option<String>,
+    /// Field to sort submissions by
+    #[arg(long, value_enum, default_value_t = SubmissionSortKey::Date)]
+    pub sort: SubmissionSortKey,
+    /// Maximum number of pages of submissions to fetch, starting from the most recent
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(i32).range(1..))]
+    pub limit: i32,
+    /// Unit used when displaying memory usage
+    #[arg(long, value_enum, default_value_t = MemoryUnit::Mb)]
+    pub memory_unit: MemoryUnit,
+    /// Print one tab-separated line per submission (id, problem, verdict, points, date) without
+    /// styling, for scripts
+    #[arg(long, conflicts_with = "output")]
+    pub oneline: bool,
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+    pub output: OutputFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum SubmissionSortKey {
+    /// Most recent first
+    Date,
+    /// Slowest first
+    Time,
+    /// Most memory first
+    Memory,
+    /// Most points first
+    Points,
+}
+
+#[derive(Args)]
+pub struct WatchDirArgs {
+    /// Directory to watch
+    pub dir: std::path::PathBuf,
+    /// Problem code, determined from the file name if not given
+    #[arg(short, long)]
+    pub problem: Option<String>,
+    /// API token
+    #[arg(short, long)]
+    pub token: Option<String>,
+    /// Submission language, determined from the file extension if not given
     #[arg(short, long)]
     pub language: Option<String>,
+    /// Unit used when displaying memory usage
+    #[arg(long, value_enum, default_value_t = MemoryUnit::Mb)]
+    pub memory_unit: MemoryUnit,
+}
+
+#[derive(Args)]
+pub struct ProblemInfoArgs {
+    /// Problem code or URL
+    pub problem: String,
+    /// API token
+    #[arg(short, long)]
+    pub token: Option<String>,
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+    pub output: OutputFormat,
+}
+
+#[derive(Args)]
+pub struct ResubmitArgs {
+    /// Id of the submission to resubmit
+    pub submission_id: i32,
+    /// API token
+    #[arg(short, long)]
+    pub token: Option<String>,
+    /// Submit without asking for confirmation, even if `confirm_before_submit` is set
+    #[arg(short, long)]
+    pub yes: bool,
+}
+
+#[derive(Args)]
+pub struct ReplayArgs {
+    /// Saved submission, e.g. `results/1234567.json`
+    pub file: std::path::PathBuf,
+    /// Unit used when displaying memory usage
+    #[arg(long, value_enum, default_value_t = MemoryUnit::Mb)]
+    pub memory_unit: MemoryUnit,
+    /// Show cases as a single line of verdict glyphs, only printing failed cases in full
+    #[arg(long)]
+    pub compact_cases: bool,
+    /// Show each batch as its own line of cases with its points
+    #[arg(long, conflicts_with = "compact_cases")]
+    pub batch_bars: bool,
+    /// Show the judge server that ran each case, if the judge reported it
+    #[arg(long)]
+    pub show_judge: bool,
+    /// Show the checker's feedback below failed cases
+    #[arg(long)]
+    pub show_feedback: bool,
+    /// Point out the first case that wasn't accepted
+    #[arg(long)]
+    pub first_failure: bool,
+}
+
+#[derive(Args)]
+pub struct VersionArgs {
+    /// Also look up the latest release on crates.io and tell whether it is newer
+    #[arg(long)]
+    pub check: bool,
 }
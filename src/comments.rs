@@ -0,0 +1,182 @@
+use anyhow::{anyhow, Result};
+
+/// Comment and string syntax of a family of languages
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Syntax {
+    /// `//` and `/* */` comments, with C++'s raw strings and digit separators
+    C,
+    /// `//` and `/* */` comments, with text blocks
+    Java,
+    /// `#` comments, with long strings
+    Python,
+}
+
+impl Syntax {
+    fn from_language(language: &str) -> Option<Self> {
+        let key = language.to_lowercase();
+        if ["c", "c11", "c18", "c23"].contains(&key.as_str())
+            || key.starts_with("cpp")
+            || key.starts_with("clang")
+        {
+            Some(Syntax::C)
+        } else if key.starts_with("java") {
+            Some(Syntax::Java)
+        } else if key.starts_with("py") {
+            Some(Syntax::Python)
+        } else {
+            None
+        }
+    }
+
+    fn c_comments(self) -> bool {
+        self != Syntax::Python
+    }
+}
+
+/// Remove the comments from `source`, written in the language with key `language`, e.g. to get
+/// below a size limit. String and character literals are left alone. Lines that only held a
+/// comment are removed entirely.
+pub fn strip_comments(source: &str, language: &str) -> Result<String> {
+    let syntax = Syntax::from_language(language).ok_or_else(|| {
+        anyhow!(
+            "--strip-comments supports C, C++, Java, and Python, not language `{}`",
+            language
+        )
+    })?;
+    let chars = source.chars().collect::<Vec<_>>();
+    let mut out = String::with_capacity(source.len());
+    // the current line, so that it can be dropped if nothing but a comment was on it
+    let mut line = String::new();
+    let mut line_had_comment = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '\n' => {
+                end_line(&mut out, &mut line, line_had_comment);
+                line_had_comment = false;
+                i += 1;
+            }
+            '/' if syntax.c_comments() && next == Some('/') => {
+                line_had_comment = true;
+                i = skip_line_comment(&chars, i);
+            }
+            '#' if syntax == Syntax::Python => {
+                line_had_comment = true;
+                i = skip_line_comment(&chars, i);
+            }
+            '/' if syntax.c_comments() && next == Some('*') => {
+                line_had_comment = true;
+                let end = find(&chars, i + 2, &['*', '/']).map_or(chars.len(), |end| end + 2);
+                // a comment separates tokens like a space does, e.g. in `int/**/x`
+                line.push(' ');
+                i = end;
+            }
+            '"' | '\'' => {
+                let end = literal_end(&chars, i, syntax, &line).min(chars.len());
+                line.extend(&chars[i..end]);
+                i = end;
+            }
+            _ => {
+                line.push(c);
+                i += 1;
+            }
+        }
+    }
+    if !line.is_empty() {
+        end_line(&mut out, &mut line, line_had_comment);
+        // the source didn't end with a newline
+        if out.ends_with('\n') {
+            out.pop();
+        }
+    }
+    Ok(out)
+}
+
+/// Add `line` to `out`, unless all it had was a comment
+fn end_line(out: &mut String, line: &mut String, had_comment: bool) {
+    if had_comment {
+        let trimmed = line.trim_end();
+        if !trimmed.is_empty() {
+            out.push_str(trimmed);
+            out.push('\n');
+        }
+    } else {
+        out.push_str(line);
+        out.push('\n');
+    }
+    line.clear();
+}
+
+/// Index of the newline ending the line comment at `start`, which is kept
+fn skip_line_comment(chars: &[char], start: usize) -> usize {
+    chars[start..]
+        .iter()
+        .position(|&c| c == '\n')
+        .map_or(chars.len(), |offset| start + offset)
+}
+
+/// Index of the first occurrence of `pattern` at or after `start`
+fn find(chars: &[char], start: usize, pattern: &[char]) -> Option<usize> {
+    (start..chars.len()).find(|&i| chars[i..].starts_with(pattern))
+}
+
+/// Index just past the string or character literal starting with the quote at `start`. `line`
+/// is the code before it on the same line, used to tell prefixes apart.
+fn literal_end(chars: &[char], start: usize, syntax: Syntax, line: &str) -> usize {
+    let quote = chars[start];
+    // identifier or number directly before the quote
+    let token = line
+        .chars()
+        .rev()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .collect::<String>()
+        .chars()
+        .rev()
+        .collect::<String>();
+    if syntax == Syntax::C {
+        // digit separators, e.g. `1'000'000`
+        if quote == '\'' && token.starts_with(|c: char| c.is_ascii_digit()) {
+            return start + 1;
+        }
+        // C++ raw strings, e.g. `R"(// not a comment)"` or `R"x(...)x"`
+        if quote == '"' && ["R", "u8R", "uR", "UR", "LR"].contains(&token.as_str()) {
+            let Some(open) = chars[start..].iter().position(|&c| c == '(') else {
+                return chars.len();
+            };
+            let delimiter = &chars[start + 1..start + open];
+            let mut terminator = vec![')'];
+            terminator.extend(delimiter);
+            terminator.push('"');
+            return find(chars, start + open, &terminator)
+                .map_or(chars.len(), |end| end + terminator.len());
+        }
+    }
+    // Python's long strings and Java's text blocks
+    let triple = [quote; 3];
+    if syntax != Syntax::C && chars[start..].starts_with(&triple) {
+        let mut i = start + 3;
+        while i < chars.len() {
+            if chars[i] == '\\' {
+                i += 2;
+            } else if chars[i..].starts_with(&triple) {
+                return i + 3;
+            } else {
+                i += 1;
+            }
+        }
+        return chars.len();
+    }
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            // unterminated literals end with the line
+            '\n' => return i,
+            c if c == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    chars.len()
+}
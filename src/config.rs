@@ -1,9 +1,26 @@
-use anyhow::{Context, Result};
+use crate::api::APIProblem;
+use crate::theme::Theme;
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use indicatif::ProgressStyle;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, path::PathBuf, time::Duration};
 
 pub const CONFY_APP_NAME: &str = "dmoj-submit";
 pub const CONFY_CONFIG_NAME: &str = "config";
+pub const CONFY_STATE_NAME: &str = "state";
+/// Name of the project-local configuration file, which overrides the global configuration
+pub const LOCAL_CONFIG_FILE_NAME: &str = ".dmojrc";
+
+/// How to authenticate with the judge
+#[derive(Serialize, Deserialize, ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMethod {
+    /// API token (default)
+    Token,
+    /// Session cookie of a logged in browser session
+    SessionCookie,
+}
 
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct ConfyConfig {
@@ -11,18 +28,429 @@ pub struct ConfyConfig {
     pub token: Option<String>,
     /// File extension -> language key mapping
     pub ext_key_map: Option<HashMap<String, String>>,
+    /// Problem code used when none is specified
+    pub default_problem: Option<String>,
+    /// DMOJ username
+    pub username: Option<String>,
+    /// Base URL of the judge to use instead of DMOJ, e.g. pinned by a project's `.dmojrc`
+    pub judge_url: Option<String>,
+    /// Language key -> shell command that transforms the source before submitting (with `--preprocess`)
+    pub preprocess: Option<HashMap<String, String>>,
+    /// How to authenticate, defaults to using the API token
+    pub auth: Option<AuthMethod>,
+    /// Value of the `sessionid` cookie, used when authenticating with a session cookie
+    pub session_cookie: Option<String>,
+    /// Names of the fields of the submission form, for judges that renamed them
+    pub form_fields: Option<FormFields>,
+    /// Ask for confirmation before every submission, e.g. during rated contests
+    pub confirm_before_submit: Option<bool>,
+    /// Look up the latest release on crates.io once a day and point out when it is newer
+    pub check_for_updates: Option<bool>,
+    /// Judge base URL -> API token, for submitting to other judges with `--judge-url`
+    pub judge_tokens: Option<HashMap<String, String>>,
+    /// UTC offset to show dates with, e.g. `+02:00`, instead of the local timezone's
+    pub utc_offset: Option<String>,
+    /// Case status -> style mapping, e.g. `TLE = "yellow"`
+    pub theme: Option<HashMap<String, String>>,
+    /// Look of the spinner shown while grading
+    pub spinner: Option<SpinnerSettings>,
+    /// Judge base URL -> how the judge spells problem codes, for judges with unusual codes
+    pub problem_codes: Option<HashMap<String, ProblemCodeScheme>>,
+}
+
+/// Look of the spinner. Unset values keep the defaults.
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct SpinnerSettings {
+    /// Milliseconds between spinner frames
+    pub tick_ms: Option<u64>,
+    /// indicatif template, e.g. `{spinner:.cyan} {msg}`
+    pub template: Option<String>,
+    /// Spinner frames, the last one is shown once grading finishes
+    pub tick_chars: Option<String>,
+}
+
+impl SpinnerSettings {
+    pub fn tick_interval(&self) -> Duration {
+        Duration::from_millis(self.tick_ms.unwrap_or(120))
+    }
+
+    /// Check the settings up front, so mistakes aren't only noticed after submitting
+    pub fn validate(&self) -> Result<()> {
+        if self.tick_ms == Some(0) {
+            return Err(anyhow!("spinner tick_ms must be positive"));
+        }
+        if let Some(template) = &self.template {
+            ProgressStyle::with_template(template)
+                .with_context(|| format!("invalid spinner template `{}`", template))?;
+        }
+        if self
+            .tick_chars
+            .as_ref()
+            .is_some_and(|chars| chars.chars().count() < 2)
+        {
+            return Err(anyhow!("spinner tick_chars needs at least two characters"));
+        }
+        Ok(())
+    }
+}
+
+/// How a judge spells problem codes, applied to problem codes before submitting. Unset values
+/// leave codes as they are given.
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct ProblemCodeScheme {
+    /// Case to convert codes to
+    pub case: Option<CodeCase>,
+    /// Prefix of every code, added to codes that don't start with it yet, e.g. `ex_`
+    pub prefix: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum CodeCase {
+    Lower,
+    Upper,
+}
+
+impl ProblemCodeScheme {
+    pub fn normalize(&self, code: &str) -> String {
+        let code = match self.case {
+            Some(CodeCase::Lower) => code.to_lowercase(),
+            Some(CodeCase::Upper) => code.to_uppercase(),
+            None => code.to_string(),
+        };
+        match &self.prefix {
+            Some(prefix) if !code.starts_with(prefix.as_str()) => format!("{}{}", prefix, code),
+            _ => code,
+        }
+    }
+}
+
+/// Scheme configured for the judge at `base_url` in `problem_codes`, ignoring trailing slashes.
+/// Judges without one keep codes as they are.
+pub fn problem_code_scheme(
+    schemes: &HashMap<String, ProblemCodeScheme>,
+    base_url: &str,
+) -> ProblemCodeScheme {
+    let base_url = base_url.trim_end_matches('/');
+    schemes
+        .iter()
+        .find(|(url, _)| url.trim_end_matches('/') == base_url)
+        .map(|(_, scheme)| scheme.clone())
+        .unwrap_or_default()
+}
+
+/// Names of the fields of the submission form. Unset fields use DMOJ's names.
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct FormFields {
+    pub problem: Option<String>,
+    pub source: Option<String>,
+    pub language: Option<String>,
+    pub source_file: Option<String>,
+}
+
+impl FormFields {
+    pub fn problem(&self) -> &str {
+        self.problem.as_deref().unwrap_or("problem")
+    }
+
+    pub fn source(&self) -> &str {
+        self.source.as_deref().unwrap_or("source")
+    }
+
+    pub fn language(&self) -> &str {
+        self.language.as_deref().unwrap_or("language")
+    }
+
+    /// Field for uploading the source as a file, used for archive submissions
+    pub fn source_file(&self) -> &str {
+        self.source_file.as_deref().unwrap_or("source_file")
+    }
 }
 
-pub fn get_config_path() -> Result<std::path::PathBuf> {
+/// Local state kept between runs, not meant to be edited by hand. Empty lists and maps are left
+/// out, since TOML can't have an empty array after a table.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct State {
+    /// Submissions recently made with dmoj-submit
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recent_submissions: Vec<RecentSubmission>,
+    /// Files that recent submissions were made from, for resubmitting them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub submitted_files: Vec<SubmittedFile>,
+    /// Number of test cases seen the last time each problem was graded, used for estimating
+    /// how long grading will take
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub case_counts: HashMap<String, usize>,
+    /// Manifest path -> entries of it that were already submitted, for resuming interrupted
+    /// batches with `--resume`
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "toml::ser::tables_last"
+    )]
+    pub batches: HashMap<PathBuf, Vec<SubmittedFile>>,
+    /// Problem page URL -> the problem's details as last fetched, for showing the problem's
+    /// name and limits when they can't be fetched
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub problems: HashMap<String, APIProblem>,
+    /// Latest release found by the last update check
+    pub latest_version: Option<VersionCheck>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VersionCheck {
+    pub version: String,
+    /// Seconds since the Unix epoch
+    pub checked_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SubmittedFile {
+    pub id: String,
+    pub path: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecentSubmission {
+    pub id: String,
+    pub problem: String,
+    /// Hash of the submitted source code, as a hex string
+    pub source_hash: String,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+pub fn get_config_path() -> Result<PathBuf> {
     confy::get_configuration_file_path(CONFY_APP_NAME, CONFY_CONFIG_NAME)
         .with_context(|| "could not get the configuration file path")
 }
 
+/// Replace `${VAR}` in `value` with the value of the environment variable `VAR`
+fn expand_env(value: &str) -> Result<String> {
+    let mut expanded = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .with_context(|| format!("unterminated `${{` in `{}`", value))?;
+        let name = &after[..end];
+        let var = std::env::var(name)
+            .with_context(|| format!("environment variable {} is not set", name))?;
+        expanded.push_str(&var);
+        rest = &after[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+impl ConfyConfig {
+    /// Theme for case statuses, with the configured styles applied over the defaults
+    pub fn theme(&self) -> Theme {
+        self.theme
+            .as_ref()
+            .map(Theme::with_overrides)
+            .unwrap_or_default()
+    }
+
+    /// Override values in `self` with the ones set in `other`
+    fn merge(&mut self, other: ConfyConfig) {
+        if other.token.is_some() {
+            self.token = other.token;
+        }
+        if let Some(other_map) = other.ext_key_map {
+            self.ext_key_map
+                .get_or_insert_with(HashMap::new)
+                .extend(other_map);
+        }
+        if other.default_problem.is_some() {
+            self.default_problem = other.default_problem;
+        }
+        if other.username.is_some() {
+            self.username = other.username;
+        }
+        if other.judge_url.is_some() {
+            self.judge_url = other.judge_url;
+        }
+        if other.auth.is_some() {
+            self.auth = other.auth;
+        }
+        if other.session_cookie.is_some() {
+            self.session_cookie = other.session_cookie;
+        }
+        if let Some(other_fields) = other.form_fields {
+            let fields = self.form_fields.get_or_insert_with(FormFields::default);
+            if other_fields.problem.is_some() {
+                fields.problem = other_fields.problem;
+            }
+            if other_fields.source.is_some() {
+                fields.source = other_fields.source;
+            }
+            if other_fields.language.is_some() {
+                fields.language = other_fields.language;
+            }
+            if other_fields.source_file.is_some() {
+                fields.source_file = other_fields.source_file;
+            }
+        }
+        if other.confirm_before_submit.is_some() {
+            self.confirm_before_submit = other.confirm_before_submit;
+        }
+        if other.check_for_updates.is_some() {
+            self.check_for_updates = other.check_for_updates;
+        }
+        if let Some(other_map) = other.judge_tokens {
+            self.judge_tokens
+                .get_or_insert_with(HashMap::new)
+                .extend(other_map);
+        }
+        if let Some(other_spinner) = other.spinner {
+            let spinner = self.spinner.get_or_insert_with(Default::default);
+            if other_spinner.tick_ms.is_some() {
+                spinner.tick_ms = other_spinner.tick_ms;
+            }
+            if other_spinner.template.is_some() {
+                spinner.template = other_spinner.template;
+            }
+            if other_spinner.tick_chars.is_some() {
+                spinner.tick_chars = other_spinner.tick_chars;
+            }
+        }
+        if other.utc_offset.is_some() {
+            self.utc_offset = other.utc_offset;
+        }
+        if let Some(other_map) = other.problem_codes {
+            self.problem_codes
+                .get_or_insert_with(HashMap::new)
+                .extend(other_map);
+        }
+        if let Some(other_map) = other.theme {
+            self.theme
+                .get_or_insert_with(HashMap::new)
+                .extend(other_map);
+        }
+        if let Some(other_map) = other.preprocess {
+            self.preprocess
+                .get_or_insert_with(HashMap::new)
+                .extend(other_map);
+        }
+    }
+
+    /// Expand environment variables in all string values, so that shared configurations don't
+    /// have to contain secrets
+    fn expand_env_vars(&mut self) -> Result<()> {
+        let optional_values = [
+            &mut self.token,
+            &mut self.default_problem,
+            &mut self.username,
+            &mut self.session_cookie,
+        ];
+        for value in optional_values.into_iter().flatten() {
+            *value = expand_env(value)?;
+        }
+        for value in self.judge_tokens.iter_mut().flat_map(HashMap::values_mut) {
+            *value = expand_env(value)?;
+        }
+        // preprocessing commands are left alone, the shell expands variables in them anyway
+        for value in self.ext_key_map.iter_mut().flat_map(HashMap::values_mut) {
+            *value = expand_env(value)?;
+        }
+        if let Some(fields) = &mut self.form_fields {
+            for value in [
+                &mut fields.problem,
+                &mut fields.source,
+                &mut fields.language,
+                &mut fields.source_file,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                *value = expand_env(value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Load only the global configuration, e.g. to modify it
+pub fn get_global_config() -> Result<ConfyConfig> {
+    confy::load(CONFY_APP_NAME, CONFY_CONFIG_NAME).map_err(|err| {
+        let path = get_config_path()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|_| "file".to_string());
+        match err {
+            // point out exactly what is wrong, e.g. which key has the wrong type
+            confy::ConfyError::BadTomlData(toml_err) => {
+                anyhow::Error::new(toml_err).context(format!("configuration {} is invalid", path))
+            }
+            err => anyhow::Error::new(err).context("could not load configuration"),
+        }
+    })
+}
+
+/// Find the nearest `.dmojrc` in the current directory or its ancestors
+pub fn find_local_config() -> Option<PathBuf> {
+    std::env::current_dir()
+        .ok()?
+        .ancestors()
+        .map(|dir| dir.join(LOCAL_CONFIG_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+/// Load the global configuration with the nearest `.dmojrc` merged over it
 pub fn get_config() -> Result<ConfyConfig> {
-    confy::load(CONFY_APP_NAME, CONFY_CONFIG_NAME).with_context(|| "could not load configuration")
+    let mut cfg = get_global_config()?;
+    if let Some(path) = find_local_config() {
+        log::info!("Using local configuration {}", path.display());
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("could not read {}", path.display()))?;
+        let local_cfg: ConfyConfig = toml::from_str(&contents)
+            .with_context(|| format!("could not parse {}", path.display()))?;
+        cfg.merge(local_cfg);
+    }
+    cfg.expand_env_vars()
+        .with_context(|| "could not expand environment variables in configuration")?;
+    if let Some(offset) = &cfg.utc_offset {
+        let offset_secs = crate::date::parse_offset(offset)
+            .with_context(|| format!("invalid utc_offset `{}`, expected e.g. `+02:00`", offset))?;
+        crate::date::set_display_offset(offset_secs);
+    }
+    if let Some(spinner) = &cfg.spinner {
+        spinner.validate()?;
+    }
+    Ok(cfg)
 }
 
 pub fn set_config(cfg: ConfyConfig) -> Result<()> {
     confy::store(CONFY_APP_NAME, CONFY_CONFIG_NAME, cfg)
-        .with_context(|| "could not store configuration")
+        .with_context(|| "could not store configuration")?;
+    // the configuration contains secrets, so only the owner should be able to read it
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(get_config_path()?, std::fs::Permissions::from_mode(0o600))
+            .with_context(|| "could not restrict configuration file permissions")?;
+    }
+    Ok(())
+}
+
+pub fn get_state() -> Result<State> {
+    confy::load(CONFY_APP_NAME, CONFY_STATE_NAME).with_context(|| "could not load local state")
+}
+
+pub fn set_state(state: State) -> Result<()> {
+    confy::store(CONFY_APP_NAME, CONFY_STATE_NAME, state)
+        .with_context(|| "could not store local state")
+}
+
+/// Delete the local state, returning the paths of the files that were removed
+pub fn clear_cache() -> Result<Vec<PathBuf>> {
+    let state_path = confy::get_configuration_file_path(CONFY_APP_NAME, CONFY_STATE_NAME)
+        .with_context(|| "could not get the state file path")?;
+    match std::fs::remove_file(&state_path) {
+        Ok(()) => Ok(vec![state_path]),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err).with_context(|| format!("could not remove {}", state_path.display())),
+    }
 }
@@ -0,0 +1,188 @@
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// UTC offset in seconds that dates are shown in, see `set_display_offset`
+static DISPLAY_OFFSET: OnceLock<i64> = OnceLock::new();
+
+/// Show dates with this UTC offset instead of the local one. Only the first call has an effect,
+/// so that `--utc` can take precedence over the configured offset.
+pub fn set_display_offset(offset_secs: i64) {
+    let _ = DISPLAY_OFFSET.set(offset_secs);
+}
+
+fn display_offset() -> i64 {
+    *DISPLAY_OFFSET.get_or_init(|| local_offset().unwrap_or(0))
+}
+
+/// UTC offset of the local timezone, asked from `date` since std doesn't know about timezones
+fn local_offset() -> Option<i64> {
+    let output = Command::new("date").arg("+%z").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_offset(String::from_utf8_lossy(&output.stdout).trim())
+}
+
+/// Parse a UTC offset like `+02:00`, `-0530`, `Z` or `UTC` into seconds
+pub fn parse_offset(offset: &str) -> Option<i64> {
+    if offset.eq_ignore_ascii_case("z") || offset.eq_ignore_ascii_case("utc") {
+        return Some(0);
+    }
+    let sign = match offset.as_bytes().first()? {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let digits = offset[1..].replace(':', "");
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = digits.split_at(2);
+    let (hours, minutes) = (hours.parse::<i64>().ok()?, minutes.parse::<i64>().ok()?);
+    if hours > 14 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Parse an RFC 3339 date as returned by the DMOJ API (e.g. `2023-05-23T19:43:02.123456+00:00`)
+/// into seconds since the Unix epoch
+pub fn parse_date(date: &str) -> Option<i64> {
+    let (day, time) = date.split_once('T')?;
+    let mut day_parts = day.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (
+        day_parts.next()?.ok()?,
+        day_parts.next()?.ok()?,
+        day_parts.next()?.ok()?,
+    );
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // split off the UTC offset, which is either `Z` or `±HH:MM`
+    let (time, offset_secs) = if let Some(time) = time.strip_suffix('Z') {
+        (time, 0)
+    } else {
+        let sign_idx = time.rfind(['+', '-'])?;
+        let (time, offset) = time.split_at(sign_idx);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let (hours, minutes) = offset[1..].split_once(':')?;
+        let offset_secs = hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60;
+        (time, sign * offset_secs)
+    };
+    let mut time_parts = time.splitn(3, ':');
+    let hours = time_parts.next()?.parse::<i64>().ok()?;
+    let minutes = time_parts.next()?.parse::<i64>().ok()?;
+    // ignore fractional seconds
+    let seconds = time_parts.next()?.split('.').next()?.parse::<i64>().ok()?;
+
+    // https://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    Some(days * 86400 + hours * 3600 + minutes * 60 + seconds - offset_secs)
+}
+
+/// Next time the clock in the display timezone shows `time` (`HH:MM` or `HH:MM:SS`), as seconds
+/// since the Unix epoch. Times that already passed today are taken to mean tomorrow.
+pub fn next_time_of_day(time: &str) -> Option<i64> {
+    let mut parts = time.splitn(3, ':').map(str::parse::<i64>);
+    let hours = parts.next()?.ok()?;
+    let minutes = parts.next()?.ok()?;
+    let seconds = parts.next().transpose().ok()?.unwrap_or(0);
+    if !(0..24).contains(&hours) || !(0..60).contains(&minutes) || !(0..60).contains(&seconds) {
+        return None;
+    }
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+    let offset = display_offset();
+    let local_midnight = (now + offset).div_euclid(86400) * 86400 - offset;
+    let target = local_midnight + hours * 3600 + minutes * 60 + seconds;
+    Some(if target <= now {
+        target + 86400
+    } else {
+        target
+    })
+}
+
+/// Render `date` in the display timezone, e.g. `2023-05-23 21:43:02 +02:00`. Falls back to the
+/// raw string if it can't be parsed.
+///
+/// The local timezone's offset is the one in effect now, so dates from the other side of a
+/// daylight saving time change are shown an hour off (with their offset, so they are still
+/// unambiguous).
+pub fn format_local(date: &str) -> String {
+    let Some(timestamp) = parse_date(date) else {
+        return date.to_string();
+    };
+    let offset = display_offset();
+    let local = timestamp + offset;
+    let (days, secs) = (local.div_euclid(86400), local.rem_euclid(86400));
+
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    let offset_label = if offset == 0 {
+        "UTC".to_string()
+    } else {
+        format!(
+            "{}{:02}:{:02}",
+            if offset < 0 { '-' } else { '+' },
+            offset.abs() / 3600,
+            offset.abs() % 3600 / 60
+        )
+    };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} {}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+        offset_label
+    )
+}
+
+/// Render `date` relative to now, e.g. "3 minutes ago". Falls back to the raw string if it
+/// can't be parsed.
+pub fn format_relative(date: &str) -> String {
+    let Some(timestamp) = parse_date(date) else {
+        return date.to_string();
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or(timestamp);
+    let elapsed = now - timestamp;
+    if elapsed < 0 {
+        return "in the future".to_string();
+    }
+    let (amount, unit) = match elapsed {
+        0..=59 => return "just now".to_string(),
+        60..=3599 => (elapsed / 60, "minute"),
+        3600..=86399 => (elapsed / 3600, "hour"),
+        86400..=2591999 => (elapsed / 86400, "day"),
+        2592000..=31535999 => (elapsed / 2592000, "month"),
+        _ => (elapsed / 31536000, "year"),
+    };
+    format!(
+        "{} {}{} ago",
+        amount,
+        unit,
+        if amount == 1 { "" } else { "s" }
+    )
+}
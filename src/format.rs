@@ -0,0 +1,179 @@
+use crate::api::APISubmission;
+use crate::cli::MemoryUnit;
+use anyhow::{anyhow, Result};
+use console::style;
+
+/// Format an amount of memory given in KB (as returned by the API) using `unit`
+pub fn format_memory(memory: f64, unit: MemoryUnit) -> String {
+    match unit {
+        MemoryUnit::Kb => format!("{:.0} KB", memory),
+        MemoryUnit::Mb => format!("{:.2} MB", memory / 1024.0),
+        MemoryUnit::Auto if memory < 1024.0 => format!("{:.0} KB", memory),
+        MemoryUnit::Auto if memory < 1024.0 * 1024.0 => format!("{:.2} MB", memory / 1024.0),
+        MemoryUnit::Auto => format!("{:.2} GB", memory / (1024.0 * 1024.0)),
+    }
+}
+
+/// Line-by-line diff of `expected` and `actual`, with removed lines prefixed by `-` in red and
+/// added lines by `+` in green
+pub fn format_diff(expected: &str, actual: &str) -> Vec<String> {
+    let (old, new): (Vec<&str>, Vec<&str>) = (expected.lines().collect(), actual.lines().collect());
+    // lengths of the longest common subsequences of the suffixes of both
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(format!("  {}", old[i]));
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            lines.push(style(format!("+ {}", new[j])).green().to_string());
+            j += 1;
+        } else {
+            lines.push(style(format!("- {}", old[i])).red().to_string());
+            i += 1;
+        }
+    }
+    lines
+}
+
+/// Format the total time of a submission, which is meaningless for TLE verdicts
+pub fn format_time(submission: &APISubmission) -> String {
+    match submission.time {
+        Some(time) if submission.result.as_deref() != Some("TLE") => format!("{:.3}s", time),
+        _ => "---".to_string(),
+    }
+}
+
+/// Format the score of a submission as a percentage, or `None` if it has no points at all
+pub fn format_percentage(submission: &APISubmission) -> Option<String> {
+    (submission.case_total > 0.0).then(|| {
+        format!(
+            "{:.0}%",
+            submission.case_points / submission.case_total * 100.0
+        )
+    })
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Placeholder {
+    Problem,
+    Verdict,
+    Points,
+    Total,
+    Percentage,
+    Time,
+    Memory,
+}
+
+impl Placeholder {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "problem" => Some(Self::Problem),
+            "verdict" => Some(Self::Verdict),
+            "points" => Some(Self::Points),
+            "total" => Some(Self::Total),
+            "percentage" => Some(Self::Percentage),
+            "time" => Some(Self::Time),
+            "memory" => Some(Self::Memory),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// User-defined template for the final summary, e.g. `{problem} {verdict} {points}/{total}`.
+/// `{{` and `}}` produce literal braces.
+#[derive(Debug, Clone)]
+pub struct SummaryFormat {
+    segments: Vec<Segment>,
+}
+
+impl SummaryFormat {
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(anyhow!("unterminated `{{` in --format")),
+                        }
+                    }
+                    let placeholder = Placeholder::from_name(&name).ok_or_else(|| {
+                        anyhow!(
+                            "unknown placeholder `{{{}}}` in format, expected one of {{problem}}, {{verdict}}, {{points}}, {{total}}, {{percentage}}, {{time}}, {{memory}}",
+                            name
+                        )
+                    })?;
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Placeholder(placeholder));
+                }
+                '}' => return Err(anyhow!("unmatched `}}` in format")),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self { segments })
+    }
+
+    pub fn render(&self, submission: &APISubmission, memory_unit: MemoryUnit) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(literal) => literal.clone(),
+                Segment::Placeholder(Placeholder::Problem) => submission.problem.clone(),
+                Segment::Placeholder(Placeholder::Verdict) => submission
+                    .result
+                    .clone()
+                    .unwrap_or_else(|| submission.status.clone()),
+                Segment::Placeholder(Placeholder::Points) => {
+                    format!("{:.0}", submission.case_points)
+                }
+                Segment::Placeholder(Placeholder::Total) => {
+                    format!("{:.0}", submission.case_total)
+                }
+                Segment::Placeholder(Placeholder::Percentage) => {
+                    format_percentage(submission).unwrap_or_else(|| "---".to_string())
+                }
+                Segment::Placeholder(Placeholder::Time) => format_time(submission),
+                Segment::Placeholder(Placeholder::Memory) => submission
+                    .memory
+                    .map(|memory| format_memory(memory, memory_unit))
+                    .unwrap_or_else(|| "---".to_string()),
+            })
+            .collect()
+    }
+}
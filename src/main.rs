@@ -1,41 +1,118 @@
 mod api;
+mod archive;
+mod batch;
 mod cli;
+mod comments;
 mod config;
+mod date;
+mod format;
+mod preprocess;
+mod prompt;
+mod resolve;
+mod sha256;
+mod status;
 mod subcommands;
+mod theme;
+mod tui;
+mod watch;
 
 use anyhow::{anyhow, Context, Result};
 use clap::Parser;
-use cli::{Cli, Commands};
-use config::{get_config, get_config_path, set_config};
-use std::{collections::HashMap, fs};
+use cli::{Cli, Commands, ProblemType};
+use config::{
+    find_local_config, get_config, get_config_path, get_global_config, set_config, ConfyConfig,
+};
+use resolve::{
+    resolve_auth, resolve_auth_with_source, resolve_file_problem, resolve_language,
+    resolve_problem, Resolved, Source, AUTO_LANGUAGE, OUTPUT_ONLY_LANGUAGE,
+};
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
     env_logger::Builder::new()
-        .filter_level(cli.verbose.log_level_filter())
+        // warnings are shown by default and `-q` hides them, but errors must always be shown
+        .filter_level(cli.verbose.log_level_filter().max(log::LevelFilter::Error))
         .init();
 
-    // TODO: add more defaults
-    /// file extension -> language key default mapping as array of tuples
-    const EXT_KEY_DEFAULT_TUPLES: [(&str, &str); 14] = [
-        ("c", "c"),
-        ("cpp", "cpp20"),
-        ("java", "java"),
-        ("kt", "kotlin"),
-        ("py", "pypy3"),
-        ("lua", "lua"),
-        ("rs", "rust"),
-        ("txt", "text"),
-        ("go", "go"),
-        ("hs", "hask"),
-        ("js", "v8js"),
-        ("nim", "nim"),
-        ("ml", "ocaml"),
-        ("zig", "zig"),
-    ];
+    // takes precedence over the configured offset, which is applied when loading the config
+    if cli.utc {
+        date::set_display_offset(0);
+    }
+    if cli.insecure {
+        eprintln!(
+            "{}",
+            console::style(
+                "WARNING: TLS certificate verification is disabled, connections are not secure!"
+            )
+            .red()
+            .bold()
+        );
+    }
+    let no_config = cli.no_config;
+    // the judge may be pinned in the configuration, e.g. for a project in its `.dmojrc`
+    let configured_judge_url = if no_config {
+        None
+    } else {
+        get_config().ok().and_then(|cfg| cfg.judge_url)
+    };
+    let mut endpoints = api::Endpoints::default().api_version(cli.api_version);
+    if let Some(url) = &configured_judge_url {
+        endpoints = endpoints.base_url(url);
+    }
+    let client_options = api::ClientOptions {
+        proxy: cli.proxy,
+        insecure: cli.insecure,
+        ca_cert: cli
+            .cacert
+            .as_deref()
+            .map(api::ClientOptions::load_ca_cert)
+            .transpose()?,
+        languages_file: cli.languages_from_file,
+        endpoints,
+    };
+    // owned, since `client_options` is moved into the subcommands
+    let judge_url = client_options.endpoints.judge_url().to_string();
+    // behave as if there were no configuration files, e.g. for reproducible runs
+    let load_config = || -> Result<ConfyConfig> {
+        if no_config {
+            log::info!("Ignoring configuration files because of --no-config");
+            Ok(ConfyConfig::default())
+        } else {
+            get_config()
+        }
+    };
+    // opt-in, and skipped for commands that deal with the configuration or the version
+    let check_updates = !matches!(
+        cli.command,
+        Commands::SetConfig(_) | Commands::GetConfig(_) | Commands::Version(_)
+    ) && !no_config
+        && get_config().is_ok_and(|cfg| cfg.check_for_updates == Some(true));
+    if check_updates {
+        subcommands::notify_if_outdated(&client_options);
+    }
     match cli.command {
+        Commands::SetConfig(_) if no_config => {
+            return Err(anyhow!("set-config cannot be used with --no-config"));
+        }
         Commands::SetConfig(conf_args) => {
-            let mut cfg = get_config()?;
+            let mut cfg = get_global_config()?;
+            if conf_args.reset {
+                if !conf_args.force
+                    && !prompt::confirm(&format!(
+                        "Reset the configuration at {}?",
+                        get_config_path()?.display()
+                    ))?
+                {
+                    return Err(anyhow!("reset aborted"));
+                }
+                let token = cfg.token.take().filter(|_| conf_args.keep_token);
+                cfg = ConfyConfig {
+                    token,
+                    ..Default::default()
+                };
+                log::info!("Reset configuration");
+            }
             if let Some(token) = conf_args.token {
                 log::info!("setting token to '{}'", token);
                 cfg.token = Some(token);
@@ -62,79 +139,716 @@ fn main() -> Result<()> {
                             .insert(ext.to_string(), lang_key.to_string());
                     });
             }
+            if let Some(default_problem) = conf_args.default_problem {
+                if default_problem.is_empty() {
+                    log::info!("Unsetting default problem");
+                    cfg.default_problem = None;
+                } else {
+                    log::info!("Set default problem to {}", default_problem);
+                    cfg.default_problem = Some(default_problem);
+                }
+            }
+            if let Some(auth) = conf_args.auth {
+                log::info!("Set authentication method to {:?}", auth);
+                cfg.auth = Some(auth);
+            }
+            if let Some(session_cookie) = conf_args.session_cookie {
+                log::info!("Set session cookie");
+                cfg.session_cookie = Some(session_cookie);
+            }
+            if let Some(username) = conf_args.username {
+                log::info!("Set username to {}", username);
+                cfg.username = Some(username);
+            }
             set_config(cfg)?;
         }
-        Commands::GetConfig => {
+        Commands::GetConfig(get_args) => {
             println!("{}", get_config_path()?.display());
-            println!("{:#?}", get_config()?);
+            if let Some(local_path) = find_local_config() {
+                println!("{}", local_path.display());
+            }
+            let cfg = load_config()?;
+            if get_args.effective {
+                let file = get_args.file.as_deref();
+                // the same resolution as submitting, including per-judge tokens and cookies
+                let auth = resolve_auth_with_source(&judge_url, None, &cfg, false).map(
+                    |(auth, source)| Resolved {
+                        value: auth.describe().to_string(),
+                        source,
+                    },
+                );
+                let settings = [
+                    (
+                        "Judge URL",
+                        Ok(Resolved {
+                            value: judge_url.clone(),
+                            source: if configured_judge_url.is_some() {
+                                Source::ConfigFile
+                            } else {
+                                Source::Default
+                            },
+                        }),
+                    ),
+                    ("Problem", resolve_problem(None, &cfg, file, false)),
+                    ("Language", resolve_language(None, &cfg, file, false)),
+                    ("Authentication", auth),
+                ];
+                for (name, resolved) in settings {
+                    match resolved {
+                        Ok(resolved) => {
+                            println!("{}: {} (from {})", name, resolved.value, resolved.source)
+                        }
+                        Err(err) => println!("{}: not set ({})", name, err),
+                    }
+                }
+            } else {
+                println!("{:#?}", cfg);
+            }
         }
-        Commands::Submit(sub_args) => {
-            let source =
-                fs::read_to_string(&sub_args.file).with_context(|| "could not read file")?;
+        Commands::Submit(mut sub_args) => {
+            // validate the format before doing anything else
+            let summary_format = sub_args
+                .format
+                .as_deref()
+                .map(format::SummaryFormat::parse)
+                .transpose()?;
+            let cfg = load_config()?;
+            let start_at = match (&sub_args.at, sub_args.delay_start) {
+                (Some(time), _) => {
+                    let timestamp = date::next_time_of_day(time).with_context(|| {
+                        format!("invalid time `{}`, expected HH:MM or HH:MM:SS", time)
+                    })?;
+                    Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(timestamp as u64))
+                }
+                (None, Some(secs)) => {
+                    Some(std::time::SystemTime::now() + std::time::Duration::from_secs(secs))
+                }
+                (None, None) => None,
+            };
+            let options = subcommands::SubmitOptions {
+                client: client_options,
+                memory_unit: sub_args.memory_unit,
+                guard_duplicates: sub_args.guard_duplicates,
+                summary_format,
+                output_dir: sub_args.output_dir,
+                queue_wait: std::time::Duration::from_secs(sub_args.wait_for_queue),
+                notify: sub_args.notify,
+                webhook: sub_args.webhook.clone(),
+                id_via_api: sub_args.id_via_api,
+                username: cfg.username.clone(),
+                first_failure: sub_args.first_failure,
+                abort_on_fail: sub_args.abort_on_fail,
+                problem_type: sub_args.problem_type,
+                multi_progress: None,
+                tui: sub_args.tui,
+                form_fields: cfg.form_fields.clone().unwrap_or_default(),
+                theme: cfg.theme(),
+                spinner: cfg.spinner.clone().unwrap_or_default(),
+                problem_codes: cfg.problem_codes.clone().unwrap_or_default(),
+                dump_request: sub_args.dump_request,
+                compact_cases: sub_args.compact_cases,
+                batch_bars: sub_args.batch_bars,
+                show_judge: sub_args.show_judge,
+                show_feedback: sub_args.show_feedback,
+                max_redirects: sub_args.max_redirects,
+                pager: sub_args.pager,
+                render_interval: sub_args
+                    .min_poll_changes
+                    .map(std::time::Duration::from_millis),
+                archive: None,
+                confirm: (sub_args.confirm || cfg.confirm_before_submit == Some(true))
+                    && !sub_args.yes,
+                source_name: None,
+                batch_entry: None,
+                raw_status: sub_args.raw_status,
+                start_at,
+                persist_progress: sub_args.persist_progress,
+                timings: sub_args.timings,
+                status: sub_args
+                    .status_fd
+                    .map(status::StatusStream::from_fd)
+                    .transpose()?
+                    .map(Arc::new),
+            };
+            let expectations = subcommands::Expectations {
+                verdict: sub_args.assert_verdict.clone(),
+                min_score: sub_args.assert_score,
+                max_time: sub_args.max_time,
+                max_memory: sub_args.max_memory,
+            };
+            if let Some(command) = &sub_args.require_local_pass {
+                log::info!("Running local tests: {}", command);
+                preprocess::run_local_tests(command)?;
+            }
+            if let Some(manifest_path) = &sub_args.manifest {
+                let mut manifest = batch::load_manifest(manifest_path)?;
+                // the same manifest may be referred to by different relative paths
+                let manifest_key = fs::canonicalize(manifest_path).with_context(|| {
+                    format!(
+                        "could not resolve manifest path {}",
+                        manifest_path.display()
+                    )
+                })?;
+                if sub_args.resume {
+                    let completed = subcommands::completed_batch_entries(&manifest_key);
+                    manifest.entries.retain(|entry| {
+                        let path = subcommands::batch_entry_path(&entry.file);
+                        let Some(done) = completed.iter().find(|done| done.path == path) else {
+                            return true;
+                        };
+                        println!(
+                            "{} {} (already submitted as {})",
+                            console::style("Skipping").bold(),
+                            entry.file.display(),
+                            done.id
+                        );
+                        false
+                    });
+                    if manifest.entries.is_empty() {
+                        println!("All entries were already submitted");
+                        subcommands::clear_batch(&manifest_key)?;
+                        return Ok(());
+                    }
+                } else {
+                    subcommands::clear_batch(&manifest_key)?;
+                }
+                let auth = resolve_auth(&judge_url, sub_args.token.as_deref(), &cfg, true)?;
+                let submit_entry =
+                    |entry: &batch::ManifestEntry, options: &subcommands::SubmitOptions| {
+                        let source = read_source(&entry.file)?;
+                        let problem =
+                            resolve_file_problem(entry.problem.as_deref(), &entry.file, false)?;
+                        let language = resolve_language(
+                            entry.language.as_deref(),
+                            &cfg,
+                            Some(&entry.file),
+                            false,
+                        )?;
+                        let source = if sub_args.preprocess {
+                            preprocess_source(&cfg, &language.value, &source)?
+                        } else {
+                            source
+                        };
+                        let options = subcommands::SubmitOptions {
+                            source_name: Some(entry.file.display().to_string()),
+                            batch_entry: Some(subcommands::BatchEntry {
+                                manifest: manifest_key.clone(),
+                                file: entry.file.clone(),
+                            }),
+                            ..options.clone()
+                        };
+                        let submission = subcommands::submit(
+                            &problem.value,
+                            &source,
+                            &auth,
+                            &language.value,
+                            &options,
+                        )?;
+                        if let Err(err) =
+                            subcommands::record_submitted_file(submission.id, &entry.file)
+                        {
+                            log::warn!("Could not record submitted file in local state: {:#}", err);
+                        }
+                        subcommands::check_outcome(&submission, &expectations)?;
+                        Ok(submission)
+                    };
+                let name = |entry: &batch::ManifestEntry| entry.file.display().to_string();
+                let jobs = sub_args.jobs.unwrap_or(1);
+                if jobs > 1 {
+                    if options.confirm {
+                        return Err(anyhow!(
+                            "cannot ask for confirmation with --jobs, pass --yes to submit anyway"
+                        ));
+                    }
+                    let options = subcommands::SubmitOptions {
+                        multi_progress: Some(indicatif::MultiProgress::new()),
+                        ..options
+                    };
+                    let results = batch::run_parallel(
+                        &manifest.entries,
+                        jobs.into(),
+                        subcommands::SUBMISSION_DELAY,
+                        |entry| {
+                            let result = submit_entry(entry, &options);
+                            if let Err(err) = &result {
+                                log::error!("Submitting {} failed: {:#}", name(entry), err);
+                            }
+                            (name(entry), result)
+                        },
+                    );
+                    batch::finish(&results, sub_args.json)?;
+                    return subcommands::clear_batch(&manifest_key);
+                }
+                let mut results = Vec::new();
+                for (i, entry) in manifest.entries.iter().enumerate() {
+                    if i > 0 {
+                        std::thread::sleep(subcommands::SUBMISSION_DELAY);
+                    }
+                    let name = name(entry);
+                    println!("{} {}", console::style("Submitting").bold(), name);
+                    let result = submit_entry(entry, &options);
+                    if let Err(err) = &result {
+                        log::error!("Submitting {} failed: {:#}", name, err);
+                    }
+                    println!();
+                    results.push((name, result));
+                }
+                // everything was submitted, so there is nothing left to resume
+                batch::finish(&results, sub_args.json)?;
+                return subcommands::clear_batch(&manifest_key);
+            }
+            if sub_args.zip {
+                let archive = archive::zip_files(&sub_args.files)?;
+                let problem = resolve_problem(sub_args.problem.as_deref(), &cfg, None, true)?;
+                let auth = resolve_auth(&judge_url, sub_args.token.as_deref(), &cfg, true)?;
+                // clap guarantees that the language is given with --files
+                let language = sub_args.language.as_deref().unwrap();
+                log::info!(
+                    "Submitting to problem {} with an archive of {} files, {}, and language {}",
+                    problem.value,
+                    sub_args.files.len(),
+                    auth.describe(),
+                    language
+                );
+                let options = subcommands::SubmitOptions {
+                    archive: Some(archive),
+                    ..options
+                };
+                let submission =
+                    subcommands::submit(&problem.value, "", &auth, language, &options)?;
+                return subcommands::check_outcome(&submission, &expectations);
+            }
+            if sub_args.latest {
+                let latest = resolve::find_latest_source(Path::new("."), &cfg)?;
+                log::info!(
+                    "Submitting most recently modified file {}",
+                    latest.display()
+                );
+                sub_args.file = Some(latest);
+            }
+            let (source, source_name) = if let Some(url) = &sub_args.from_url {
+                (api::fetch_source(&options.client, url)?, url.clone())
+            } else {
+                // clap guarantees that either a file, a URL, a manifest or --latest is given
+                let file = sub_args.file.as_deref().unwrap();
+                match &sub_args.git_rev {
+                    Some(rev) => (
+                        read_source_at_revision(file, rev)?,
+                        format!("{} at {}", file.display(), rev),
+                    ),
+                    None => (read_source(file)?, file.display().to_string()),
+                }
+            };
 
             if source.trim().is_empty() {
-                return Err(anyhow!("file {} is empty", sub_args.file.display()));
+                return Err(anyhow!("{} is empty", source_name));
             }
 
-            let cfg = get_config()?;
-            let problem = if let Some(problem) = sub_args.problem {
-                problem
+            let problem = resolve_problem(
+                sub_args.problem.as_deref(),
+                &cfg,
+                sub_args.file.as_deref(),
+                true,
+            )?;
+            let language = match sub_args.problem_type {
+                // the output itself is submitted, so the file extension says nothing
+                ProblemType::OutputOnly => {
+                    // `auto` would go by the extension, which doesn't apply here
+                    let language = sub_args
+                        .language
+                        .as_deref()
+                        .filter(|language| *language != AUTO_LANGUAGE)
+                        .unwrap_or(OUTPUT_ONLY_LANGUAGE);
+                    if !language.eq_ignore_ascii_case(OUTPUT_ONLY_LANGUAGE) {
+                        return Err(anyhow!(
+                            "output-only problems are submitted as `{}`, not `{}`",
+                            OUTPUT_ONLY_LANGUAGE,
+                            language
+                        ));
+                    }
+                    language.to_string()
+                }
+                _ => {
+                    let language = resolve_language(
+                        sub_args.language.as_deref(),
+                        &cfg,
+                        sub_args.file.as_deref(),
+                        true,
+                    )?;
+                    if language.source == Source::Default {
+                        // built-in defaults only come from the file extension, so there is one
+                        let ext = sub_args
+                            .file
+                            .as_deref()
+                            .and_then(Path::extension)
+                            .map(|ext| ext.to_string_lossy())
+                            .unwrap_or_default();
+                        // a hint on how to avoid the guess, so it is shown by default
+                        log::warn!(
+                            "Defaulting to {} (configure with `dmoj-submit set-config --language {}:{}`)",
+                            language.value,
+                            ext,
+                            language.value
+                        );
+                    }
+                    language.value
+                }
+            };
+            let problem = problem.value;
+            let source = if sub_args.preprocess {
+                preprocess_source(&cfg, &language, &source)?
             } else {
-                // if unspecified, get problem name from file stem
-                sub_args
-                    .file
-                    .file_stem()
-                    .with_context(|| "no file name specified")?
-                    .to_str()
-                    .with_context(|| "file name is not valid Unicode")?
-                    .to_string()
-            };
-            let token = if let Some(token) = sub_args.token {
-                token
+                source
+            };
+            let source = if sub_args.strip_comments {
+                comments::strip_comments(&source, &language)?
             } else {
-                // if unspecified, get API token from configuration
-                cfg.token
-                    .with_context(|| "API token not defined in configuration")?
+                source
             };
-            let language = if let Some(language) = sub_args.language {
-                language
+            let source = if sub_args.strip_trailing {
+                strip_trailing_whitespace(&source)
             } else {
-                // if unspecified, get language from file extension + configuration
-                let file_ext = sub_args
-                    .file
-                    .extension()
-                    .with_context(|| "no file extension specified")?
-                    .to_str()
-                    .with_context(|| "file extension is not valid Unicode")?
-                    .to_string();
-                let ext_key_default_map: HashMap<String, String> = HashMap::from_iter(
-                    EXT_KEY_DEFAULT_TUPLES
-                        .into_iter()
-                        .map(|(key, val)| (key.to_string(), val.to_string())),
+                source
+            };
+            // hash exactly what would be sent, after preprocessing and stripping
+            if sub_args.print_hash || sub_args.dry_run {
+                println!(
+                    "{} {}",
+                    console::style("SHA-256:").bold(),
+                    subcommands::hash_source(&source)
                 );
-                if let Some(cfg_lang_key) =
-                    cfg.ext_key_map.and_then(|hm| hm.get(&file_ext).cloned())
+            }
+            // a single judge is just a different judge to submit to
+            let (judge_url, options) = match sub_args.judge_url.as_slice() {
+                [url] => (
+                    url.clone(),
+                    subcommands::SubmitOptions {
+                        client: api::ClientOptions {
+                            endpoints: options.client.endpoints.clone().base_url(url),
+                            ..options.client.clone()
+                        },
+                        ..options
+                    },
+                ),
+                _ => (judge_url, options),
+            };
+            if sub_args.dry_run {
+                if options.dump_request {
+                    let auth = resolve_auth(&judge_url, sub_args.token.as_deref(), &cfg, true)?;
+                    subcommands::dump_submit_request(
+                        &problem, &source, &auth, &language, &options,
+                    )?;
+                }
+                println!(
+                    "Would submit {} to problem {} with language {}",
+                    source_name, problem, language
+                );
+                return Ok(());
+            }
+            let options = subcommands::SubmitOptions {
+                source_name: Some(source_name.clone()),
+                ..options
+            };
+            // what happens after grading, whichever judge it was on
+            let finish = |submission: &api::APISubmission| -> Result<()> {
+                if let (Some(file), None) = (&sub_args.file, &sub_args.git_rev) {
+                    if let Err(err) = subcommands::record_submitted_file(submission.id, file) {
+                        log::warn!("Could not record submitted file in local state: {:#}", err);
+                    }
+                }
+                // whitespace matters for answer submissions, and is easy to get wrong
+                if language.eq_ignore_ascii_case("text")
+                    && submission.result.as_deref() == Some("WA")
+                    && !sub_args.strip_trailing
                 {
-                    cfg_lang_key
-                } else if let Some(default_lang_key) = ext_key_default_map.get(&file_ext).cloned() {
-                    log::warn!("Defaulting to {}", default_lang_key);
-                    default_lang_key
-                } else {
-                    return Err(anyhow!("could not determine language"));
+                    println!(
+                        "{}",
+                        console::style(
+                            "Hint: check for trailing whitespace, or resubmit with --strip-trailing"
+                        )
+                        .yellow()
+                    );
                 }
+                subcommands::check_outcome(submission, &expectations)
             };
+            if sub_args.judge_url.len() > 1 {
+                let judges = sub_args
+                    .judge_url
+                    .iter()
+                    .map(|url| {
+                        resolve_auth(url, sub_args.token.as_deref(), &cfg, true)
+                            .map(|auth| (url.clone(), auth))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let results =
+                    subcommands::compare_judges(&problem, &source, &language, &judges, &options)?;
+                let mut failed = 0;
+                for ((url, _), submission) in judges.iter().zip(&results) {
+                    if let Err(err) = finish(submission) {
+                        log::error!("Submission to {} failed: {:#}", url, err);
+                        failed += 1;
+                    }
+                }
+                if failed > 0 {
+                    return Err(anyhow!(
+                        "{} of {} submissions failed",
+                        failed,
+                        results.len()
+                    ));
+                }
+                return Ok(());
+            }
+            let auth = resolve_auth(&judge_url, sub_args.token.as_deref(), &cfg, true)?;
             log::info!(
-                "Submitting to problem {} with file {}, token `{}`, and language {}",
+                "Submitting to problem {} with source from {}, {}, and language {}",
                 problem,
-                sub_args.file.display(),
-                token,
+                source_name,
+                auth.describe(),
+                language
+            );
+            let submission = subcommands::submit(&problem, &source, &auth, &language, &options)?;
+            finish(&submission)?;
+        }
+        Commands::Compare(compare_args) => {
+            let cfg = load_config()?;
+            let problem =
+                resolve_file_problem(compare_args.problem.as_deref(), &compare_args.file_a, true)?;
+            let auth = resolve_auth(&judge_url, compare_args.token.as_deref(), &cfg, true)?;
+            let entries = [&compare_args.file_a, &compare_args.file_b]
+                .into_iter()
+                .map(|file| {
+                    let source = read_source(file)?;
+                    let language =
+                        resolve_language(compare_args.language.as_deref(), &cfg, Some(file), true)?;
+                    Ok((file.display().to_string(), source, language.value))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let options = subcommands::SubmitOptions {
+                client: client_options,
+                username: cfg.username.clone(),
+                form_fields: cfg.form_fields.clone().unwrap_or_default(),
+                theme: cfg.theme(),
+                spinner: cfg.spinner.clone().unwrap_or_default(),
+                problem_codes: cfg.problem_codes.clone().unwrap_or_default(),
+                confirm: cfg.confirm_before_submit == Some(true),
+                ..Default::default()
+            };
+            subcommands::compare(&problem.value, &auth, &entries, &options)?;
+        }
+        Commands::Init(init_args) => {
+            let cfg = load_config()?;
+            let ext = match init_args.ext {
+                Some(ext) => ext.trim_start_matches('.').to_string(),
+                None => resolve::extension_for_language(&init_args.language, &cfg).with_context(
+                    || {
+                        format!(
+                            "no file extension is known for language {}, pass one with --ext or add a mapping with set-config --language",
+                            init_args.language
+                        )
+                    },
+                )?,
+            };
+            let path = Path::new(&resolve::problem_code(&init_args.problem)).with_extension(ext);
+            subcommands::init(&client_options, &init_args.language, &path, init_args.force)?;
+        }
+        Commands::Version(version_args) => {
+            subcommands::version(&client_options, version_args.check)?;
+        }
+        Commands::ClearCache => {
+            let removed = config::clear_cache()?;
+            if removed.is_empty() {
+                println!("Nothing to remove");
+            }
+            for path in removed {
+                println!("Removed {}", path.display());
+            }
+        }
+        Commands::WatchDir(watch_args) => {
+            let cfg = load_config()?;
+            let auth = resolve_auth(&judge_url, watch_args.token.as_deref(), &cfg, true)?;
+            let options = subcommands::SubmitOptions {
+                client: client_options,
+                memory_unit: watch_args.memory_unit,
+                username: cfg.username.clone(),
+                form_fields: cfg.form_fields.clone().unwrap_or_default(),
+                theme: cfg.theme(),
+                spinner: cfg.spinner.clone().unwrap_or_default(),
+                problem_codes: cfg.problem_codes.clone().unwrap_or_default(),
+                confirm: cfg.confirm_before_submit == Some(true),
+                ..Default::default()
+            };
+            println!(
+                "Watching {} for changes, press Ctrl-C to stop",
+                watch_args.dir.display()
+            );
+            watch::watch(&watch_args.dir, &cfg, |file| {
+                let source = read_source(file)?;
+                let problem = resolve_file_problem(watch_args.problem.as_deref(), file, false)?;
+                let language =
+                    resolve_language(watch_args.language.as_deref(), &cfg, Some(file), false)?;
+                println!("{} {}", console::style("Submitting").bold(), file.display());
+                subcommands::submit(&problem.value, &source, &auth, &language.value, &options)?;
+                println!();
+                Ok(())
+            })?;
+        }
+        Commands::Resubmit(resubmit_args) => {
+            let cfg = load_config()?;
+            let auth = resolve_auth(&judge_url, resubmit_args.token.as_deref(), &cfg, true)?;
+            let id = resubmit_args.submission_id.to_string();
+            let original = api::get_submission(&client_options, &auth, &id)?;
+            // DMOJ's API doesn't return the source, so resubmit the file it was made from
+            let stored = subcommands::submitted_file(&id).map(|path| path.display().to_string());
+            let file = match stored {
+                Some(path) if !prompt::is_interactive() => path,
+                stored => prompt::input("Source file", stored.as_deref()).with_context(|| {
+                    format!("the file submission {} was made from is not known", id)
+                })?,
+            };
+            let file = Path::new(&file);
+            let source = read_source(file)?;
+            let language = original.language.to_lowercase();
+            log::info!(
+                "Resubmitting {} to problem {} with language {}",
+                file.display(),
+                original.problem,
                 language
             );
-            subcommands::submit(&problem, &source, &token, &language)?;
+            let options = subcommands::SubmitOptions {
+                client: client_options,
+                username: cfg.username.clone(),
+                form_fields: cfg.form_fields.clone().unwrap_or_default(),
+                theme: cfg.theme(),
+                spinner: cfg.spinner.clone().unwrap_or_default(),
+                problem_codes: cfg.problem_codes.clone().unwrap_or_default(),
+                confirm: cfg.confirm_before_submit == Some(true) && !resubmit_args.yes,
+                source_name: Some(file.display().to_string()),
+                ..Default::default()
+            };
+            let submission =
+                subcommands::submit(&original.problem, &source, &auth, &language, &options)?;
+            if let Err(err) = subcommands::record_submitted_file(submission.id, file) {
+                log::warn!("Could not record submitted file in local state: {:#}", err);
+            }
+        }
+        Commands::ProblemInfo(info_args) => {
+            let cfg = load_config()?;
+            let auth = resolve_auth(&judge_url, info_args.token.as_deref(), &cfg, true)?;
+            let problem = resolve::problem_code(&info_args.problem);
+            subcommands::problem_info(&client_options, &auth, &problem, info_args.output)?;
+        }
+        Commands::Submissions(list_args) => {
+            let cfg = load_config()?;
+            let auth = resolve_auth(&judge_url, list_args.token.as_deref(), &cfg, true)?;
+            let user =
+                list_args.user.clone().or(cfg.username).with_context(|| {
+                    "no user given, pass --user or set a username with set-config"
+                })?;
+            let problem = list_args.problem.as_deref().map(resolve::problem_code);
+            let mut filters = vec![("user", user.as_str())];
+            if let Some(problem) = &problem {
+                filters.push(("problem", problem));
+            }
+            if let Some(verdict) = &list_args.verdict {
+                filters.push(("result", verdict));
+            }
+            subcommands::list_submissions(&client_options, &auth, &filters, &list_args)?;
         }
-        Commands::ListLanguages => {
-            subcommands::list_languages()?;
+        Commands::Replay(replay_args) => {
+            let cfg = load_config()?;
+            let options = subcommands::SubmitOptions {
+                memory_unit: replay_args.memory_unit,
+                compact_cases: replay_args.compact_cases,
+                batch_bars: replay_args.batch_bars,
+                show_judge: replay_args.show_judge,
+                show_feedback: replay_args.show_feedback,
+                first_failure: replay_args.first_failure,
+                theme: cfg.theme(),
+                spinner: cfg.spinner.clone().unwrap_or_default(),
+                ..Default::default()
+            };
+            subcommands::replay(&replay_args.file, &options)?;
+        }
+        Commands::ListLanguages(list_args) => {
+            subcommands::list_languages(&client_options, list_args.sort_by, list_args.output)?;
         }
     };
     Ok(())
 }
+
+/// Read the source code in `file`, making sure it isn't empty
+fn read_source(file: &Path) -> Result<String> {
+    let source = fs::read_to_string(file)
+        .with_context(|| format!("could not read file {}", file.display()))?;
+    if source.trim().is_empty() {
+        return Err(anyhow!("file {} is empty", file.display()));
+    }
+    Ok(source)
+}
+
+/// Read `file` as it was at git revision `rev`
+fn read_source_at_revision(file: &Path, rev: &str) -> Result<String> {
+    let file_name = file
+        .file_name()
+        .with_context(|| format!("{} is not a file", file.display()))?;
+    // run git next to the file, so that the path is relative to the current directory of git
+    let dir = file
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .arg("show")
+        .arg(format!("{}:./{}", rev, file_name.to_string_lossy()))
+        .output()
+        .with_context(|| "could not run git")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+        .with_context(|| format!("could not read {} at revision {}", file.display(), rev));
+    }
+    let source = String::from_utf8(output.stdout)
+        .with_context(|| format!("{} at revision {} is not valid UTF-8", file.display(), rev))?;
+    if source.trim().is_empty() {
+        return Err(anyhow!(
+            "file {} at revision {} is empty",
+            file.display(),
+            rev
+        ));
+    }
+    Ok(source)
+}
+
+/// Remove trailing whitespace from every line, including trailing newlines at the end
+fn strip_trailing_whitespace(source: &str) -> String {
+    source
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_end()
+        .to_string()
+}
+
+/// Run `source` through the preprocessing command configured for `language`
+fn preprocess_source(cfg: &ConfyConfig, language: &str, source: &str) -> Result<String> {
+    let command = cfg
+        .preprocess
+        .as_ref()
+        .and_then(|hm| hm.get(language))
+        .with_context(|| {
+            format!(
+                "no preprocessing command configured for language {}",
+                language
+            )
+        })?;
+    log::info!("Preprocessing source with `{}`", command);
+    let source = preprocess::run_command(command, source)?;
+    if source.trim().is_empty() {
+        return Err(anyhow!("preprocessed source is empty"));
+    }
+    Ok(source)
+}
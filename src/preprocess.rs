@@ -0,0 +1,70 @@
+use anyhow::{anyhow, Context, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// `command` to be run through the shell
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    }
+}
+
+/// Run `command` through the shell with `source` on its stdin and return its stdout
+pub fn run_command(command: &str, source: &str) -> Result<String> {
+    let mut child = shell(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("could not run preprocessing command `{}`", command))?;
+
+    // write from another thread so a command that produces output before reading all of its
+    // input can't deadlock us
+    let mut stdin = child.stdin.take().unwrap();
+    let input = source.to_string();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("preprocessing command `{}` failed", command))?;
+    writer
+        .join()
+        .map_err(|_| anyhow!("could not write source to preprocessing command"))?
+        .with_context(|| "could not write source to preprocessing command")?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "preprocessing command `{}` exited with {}",
+            command,
+            output.status
+        ));
+    }
+    String::from_utf8(output.stdout).with_context(|| {
+        format!(
+            "output of preprocessing command `{}` is not valid UTF-8",
+            command
+        )
+    })
+}
+
+/// Run the local test command `command` through the shell, printing its output if it fails
+pub fn run_local_tests(command: &str) -> Result<()> {
+    let output = shell(command)
+        .stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("could not run local test command `{}`", command))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    std::io::stdout().write_all(&output.stdout)?;
+    std::io::stderr().write_all(&output.stderr)?;
+    Err(anyhow!(
+        "local test command `{}` exited with {}, not submitting",
+        command,
+        output.status
+    ))
+}
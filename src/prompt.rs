@@ -0,0 +1,45 @@
+use anyhow::{anyhow, Result};
+use console::Term;
+use std::io::IsTerminal;
+
+/// Whether there is a user around to answer prompts
+pub fn is_interactive() -> bool {
+    std::io::stdin().is_terminal() && console::user_attended_stderr()
+}
+
+/// Ask the user a yes/no question on stderr, defaulting to no.
+/// Errors if there is no user to ask.
+pub fn confirm(prompt: &str) -> Result<bool> {
+    if !is_interactive() {
+        return Err(anyhow!(
+            "{} (cannot ask for confirmation, not a terminal)",
+            prompt
+        ));
+    }
+    let term = Term::stderr();
+    term.write_str(&format!("{} [y/N] ", prompt))?;
+    let answer = term.read_line()?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Ask the user for a value on stderr until they give a non-empty one.
+/// Entering nothing selects `default` if there is one.
+pub fn input(prompt: &str, default: Option<&str>) -> Result<String> {
+    if !is_interactive() {
+        return Err(anyhow!("{} (cannot ask, not a terminal)", prompt));
+    }
+    let term = Term::stderr();
+    loop {
+        match default {
+            Some(default) => term.write_str(&format!("{} [{}]: ", prompt, default))?,
+            None => term.write_str(&format!("{}: ", prompt))?,
+        }
+        let answer = term.read_line()?;
+        let answer = answer.trim();
+        if !answer.is_empty() {
+            return Ok(answer.to_string());
+        } else if let Some(default) = default {
+            return Ok(default.to_string());
+        }
+    }
+}
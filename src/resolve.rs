@@ -0,0 +1,429 @@
+use crate::api::Auth;
+use crate::config::{AuthMethod, ConfyConfig};
+use crate::prompt;
+use anyhow::{anyhow, Context, Result};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::SystemTime,
+};
+
+// TODO: add more defaults
+/// file extension -> language key default mapping as array of tuples
+pub const EXT_KEY_DEFAULT_TUPLES: [(&str, &str); 14] = [
+    ("c", "c"),
+    ("cpp", "cpp20"),
+    ("java", "java"),
+    ("kt", "kotlin"),
+    ("py", "pypy3"),
+    ("lua", "lua"),
+    ("rs", "rust"),
+    ("txt", "text"),
+    ("go", "go"),
+    ("hs", "hask"),
+    ("js", "v8js"),
+    ("nim", "nim"),
+    ("ml", "ocaml"),
+    ("zig", "zig"),
+];
+
+/// Where the effective value of a setting came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Flag,
+    ConfigFile,
+    Default,
+    FileName,
+    Prompt,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Source::Flag => "command-line flag",
+            Source::ConfigFile => "configuration file",
+            Source::Default => "built-in default",
+            Source::FileName => "file name",
+            Source::Prompt => "prompt",
+        })
+    }
+}
+
+/// The effective value of a setting along with where it came from
+#[derive(Debug)]
+pub struct Resolved {
+    pub value: String,
+    pub source: Source,
+}
+
+impl Resolved {
+    fn new(value: impl Into<String>, source: Source) -> Self {
+        Self {
+            value: value.into(),
+            source,
+        }
+    }
+}
+
+/// Problem code pasted from the browser, e.g. `https://dmoj.ca/problem/aplusb`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProblemRef {
+    pub problem: String,
+    pub contest: Option<String>,
+}
+
+/// Parse `input` as a problem URL (`.../problem/<code>` or `.../contest/<key>/problem/<code>`),
+/// falling back to treating it as a bare problem code
+pub fn parse_problem(input: &str) -> ProblemRef {
+    let bare = || ProblemRef {
+        problem: input.to_string(),
+        contest: None,
+    };
+    let Ok(url) = reqwest::Url::parse(input) else {
+        return bare();
+    };
+    let Some(segments) = url.path_segments() else {
+        return bare();
+    };
+    let segments: Vec<&str> = segments.filter(|segment| !segment.is_empty()).collect();
+    let Some(idx) = segments.iter().rposition(|&segment| segment == "problem") else {
+        return bare();
+    };
+    let Some(problem) = segments.get(idx + 1) else {
+        return bare();
+    };
+    let contest = match segments[..idx] {
+        [.., "contest", contest] => Some(contest.to_string()),
+        _ => None,
+    };
+    ProblemRef {
+        problem: problem.to_string(),
+        contest,
+    }
+}
+
+/// Problem code for `input`, which may be a problem URL. Contest problems can only be submitted
+/// to while participating, so remind the user of that.
+pub fn problem_code(input: &str) -> String {
+    let problem_ref = parse_problem(input);
+    if let Some(contest) = &problem_ref.contest {
+        log::info!(
+            "Problem {} is from contest {}, make sure you have joined it",
+            problem_ref.problem,
+            contest
+        );
+    }
+    problem_ref.problem
+}
+
+/// `value` as a string. Parts that aren't valid Unicode are dropped with a warning, and it is
+/// only an error if nothing usable is left.
+fn lossy_str(value: &OsStr, what: &str) -> Result<String> {
+    if let Some(value) = value.to_str() {
+        return Ok(value.to_string());
+    }
+    let lossy = value
+        .to_string_lossy()
+        .replace(char::REPLACEMENT_CHARACTER, "");
+    if lossy.is_empty() {
+        return Err(anyhow!("{} is not valid Unicode", what));
+    }
+    log::warn!(
+        "Dropped invalid Unicode from {} {:?}, using `{}`",
+        what,
+        value,
+        lossy
+    );
+    Ok(lossy)
+}
+
+/// Determine the problem code. Priority: flag > configured default problem > file stem.
+/// If `allow_prompt` is set and we are running interactively, ask for it instead of failing.
+pub fn resolve_problem(
+    flag: Option<&str>,
+    cfg: &ConfyConfig,
+    file: Option<&Path>,
+    allow_prompt: bool,
+) -> Result<Resolved> {
+    problem_from(flag, cfg.default_problem.as_deref(), file, allow_prompt)
+}
+
+/// Determine the problem code for one of several files submitted at once (manifest entries,
+/// `watch-dir`, `compare`). Priority: flag > file stem. The configured default problem is
+/// ignored, since it would send every file to the same problem.
+pub fn resolve_file_problem(
+    flag: Option<&str>,
+    file: &Path,
+    allow_prompt: bool,
+) -> Result<Resolved> {
+    problem_from(flag, None, Some(file), allow_prompt)
+}
+
+fn problem_from(
+    flag: Option<&str>,
+    default_problem: Option<&str>,
+    file: Option<&Path>,
+    allow_prompt: bool,
+) -> Result<Resolved> {
+    if let Some(problem) = flag {
+        return Ok(Resolved::new(problem_code(problem), Source::Flag));
+    }
+    if let Some(default_problem) = default_problem {
+        return Ok(Resolved::new(default_problem, Source::ConfigFile));
+    }
+    let file_stem = file.and_then(Path::file_stem);
+    file_stem
+        .with_context(|| "no file name specified")
+        .and_then(|stem| lossy_str(stem, "file name"))
+        .map(|stem| Resolved::new(stem, Source::FileName))
+        .or_else(|err| {
+            if allow_prompt && prompt::is_interactive() {
+                let lossy_stem = file_stem.map(|stem| stem.to_string_lossy());
+                let problem = prompt::input("Problem code", lossy_stem.as_deref())?;
+                Ok(Resolved::new(problem_code(&problem), Source::Prompt))
+            } else {
+                Err(err)
+            }
+        })
+}
+
+/// Determine the API token. Priority: flag > configuration.
+pub fn resolve_token(
+    flag: Option<&str>,
+    cfg: &ConfyConfig,
+    allow_prompt: bool,
+) -> Result<Resolved> {
+    if let Some(token) = flag {
+        Ok(Resolved::new(token, Source::Flag))
+    } else if let Some(token) = &cfg.token {
+        Ok(Resolved::new(token, Source::ConfigFile))
+    } else if allow_prompt && prompt::is_interactive() {
+        Ok(Resolved::new(
+            prompt::input("API token", None)?,
+            Source::Prompt,
+        ))
+    } else {
+        Err(anyhow!("API token not defined in configuration"))
+    }
+}
+
+/// Determine how to authenticate with the judge at `base_url`. Priority: token flag > token
+/// configured for the judge in `judge_tokens` > configured authentication method.
+pub fn resolve_auth(
+    base_url: &str,
+    token_flag: Option<&str>,
+    cfg: &ConfyConfig,
+    allow_prompt: bool,
+) -> Result<Auth> {
+    let (auth, source) = resolve_auth_with_source(base_url, token_flag, cfg, allow_prompt)?;
+    // token formats differ between judges, so this is only a hint
+    if let Auth::Token(token) = &auth {
+        if let Some(problem) = token_problem(token) {
+            log::warn!(
+                "API token from the {} looks malformed ({}), submitting may fail",
+                source,
+                problem
+            );
+        }
+    }
+    Ok(auth)
+}
+
+/// Like `resolve_auth`, but also tells where the credentials came from, without checking them,
+/// e.g. for `get-config --effective`
+pub fn resolve_auth_with_source(
+    base_url: &str,
+    token_flag: Option<&str>,
+    cfg: &ConfyConfig,
+    allow_prompt: bool,
+) -> Result<(Auth, Source)> {
+    if token_flag.is_none() {
+        if let Some(token) = judge_token(base_url, cfg) {
+            log::info!("Using the API token configured for {}", base_url);
+            return Ok((Auth::Token(token.to_string()), Source::ConfigFile));
+        }
+    }
+    if token_flag.is_none() && cfg.auth == Some(AuthMethod::SessionCookie) {
+        let cookie = cfg.session_cookie.as_ref().with_context(|| {
+            "session cookie authentication is configured, but no session cookie is set"
+        })?;
+        return Ok((Auth::SessionCookie(cookie.clone()), Source::ConfigFile));
+    }
+    let token = resolve_token(token_flag, cfg, allow_prompt)?;
+    Ok((Auth::Token(token.value), token.source))
+}
+
+/// Token configured for the judge at `base_url` in `judge_tokens`, ignoring trailing slashes
+fn judge_token<'a>(base_url: &str, cfg: &'a ConfyConfig) -> Option<&'a str> {
+    let base_url = base_url.trim_end_matches('/');
+    cfg.judge_tokens
+        .iter()
+        .flatten()
+        .find(|(url, _)| url.trim_end_matches('/') == base_url)
+        .map(|(_, token)| token.as_str())
+}
+
+/// Shortest token that isn't obviously truncated. DMOJ's tokens are considerably longer.
+const MIN_TOKEN_LEN: usize = 16;
+
+/// Describe what looks wrong with `token`, e.g. whitespace left over from copy-pasting it
+fn token_problem(token: &str) -> Option<&'static str> {
+    if token.is_empty() {
+        Some("it is empty")
+    } else if token.trim() != token {
+        Some("it has leading or trailing whitespace")
+    } else if token.starts_with(['"', '\'']) || token.ends_with(['"', '\'']) {
+        Some("it is surrounded by quotes")
+    } else if token.len() < MIN_TOKEN_LEN {
+        Some("it is too short, it may have been truncated")
+    } else if !token
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-_+/=.:".contains(c))
+    {
+        Some("it contains unexpected characters")
+    } else {
+        None
+    }
+}
+
+/// Language flag value that forces the language to be inferred from the file extension
+pub const AUTO_LANGUAGE: &str = "auto";
+
+/// Language key of output-only problems, whose submissions are the output itself
+pub const OUTPUT_ONLY_LANGUAGE: &str = "text";
+
+/// Determine the language key. Priority: flag > configured mapping for the file extension >
+/// built-in mapping for the file extension.
+pub fn resolve_language(
+    flag: Option<&str>,
+    cfg: &ConfyConfig,
+    file: Option<&Path>,
+    allow_prompt: bool,
+) -> Result<Resolved> {
+    // `auto` explicitly asks for inference from the file extension, without prompting
+    let allow_prompt = match flag {
+        Some(AUTO_LANGUAGE) => false,
+        Some(language) => return Ok(Resolved::new(language, Source::Flag)),
+        None => allow_prompt,
+    };
+    let infer_language = || -> Result<Resolved> {
+        let file_ext = file
+            .and_then(Path::extension)
+            .with_context(|| "no file extension specified")?;
+        let file_ext = lossy_str(file_ext, "file extension")?;
+        let file_ext = file_ext.as_str();
+        if let Some(cfg_lang_key) = cfg.ext_key_map.as_ref().and_then(|hm| hm.get(file_ext)) {
+            Ok(Resolved::new(cfg_lang_key, Source::ConfigFile))
+        } else if let Some((_, default_lang_key)) = EXT_KEY_DEFAULT_TUPLES
+            .into_iter()
+            .find(|(ext, _)| *ext == file_ext)
+        {
+            Ok(Resolved::new(default_lang_key, Source::Default))
+        } else {
+            Err(anyhow!("could not determine language"))
+        }
+    };
+    match infer_language() {
+        Ok(language) => Ok(language),
+        Err(_) if allow_prompt && prompt::is_interactive() => Ok(Resolved::new(
+            prompt::input("Language key", None)?,
+            Source::Prompt,
+        )),
+        Err(err) => Err(err),
+    }
+}
+
+/// Find the file extension for a language key, the reverse of `resolve_language`
+pub fn extension_for_language(language: &str, cfg: &ConfyConfig) -> Option<String> {
+    let mut cfg_exts = cfg
+        .ext_key_map
+        .iter()
+        .flatten()
+        .filter(|(_, key)| key.as_str() == language)
+        .map(|(ext, _)| ext.as_str())
+        .collect::<Vec<_>>();
+    // HashMap order is random, so pick deterministically if several extensions match
+    cfg_exts.sort_unstable();
+    cfg_exts
+        .first()
+        .copied()
+        .or_else(|| default_extension(language))
+        .map(str::to_string)
+}
+
+/// language key -> file extension for keys that no extension maps to by default, e.g. older
+/// versions of a language
+const KEY_EXT_EXTRA_TUPLES: [(&str, &str); 9] = [
+    ("c11", "c"),
+    ("cpp03", "cpp"),
+    ("cpp11", "cpp"),
+    ("cpp14", "cpp"),
+    ("cpp17", "cpp"),
+    ("py2", "py"),
+    ("py3", "py"),
+    ("pypy2", "py"),
+    ("java8", "java"),
+];
+
+/// Canonical file extension for a language key, e.g. `cpp` for `cpp20`, the reverse of
+/// `EXT_KEY_DEFAULT_TUPLES`
+pub fn default_extension(language: &str) -> Option<&'static str> {
+    static KEY_EXT_MAP: OnceLock<HashMap<&str, &str>> = OnceLock::new();
+    let map = KEY_EXT_MAP.get_or_init(|| {
+        let mut map = HashMap::new();
+        // the built-in mapping wins, and its first extension for a key is the canonical one
+        for (ext, key) in EXT_KEY_DEFAULT_TUPLES {
+            map.entry(key).or_insert(ext);
+        }
+        for (key, ext) in KEY_EXT_EXTRA_TUPLES {
+            map.entry(key).or_insert(ext);
+        }
+        map
+    });
+    map.get(language.to_lowercase().as_str()).copied()
+}
+
+/// List the files in `dir` whose extension maps to a language key, with their modification times
+pub fn source_files(dir: &Path, cfg: &ConfyConfig) -> Result<Vec<(PathBuf, SystemTime)>> {
+    let has_mapping = |ext: &str| {
+        cfg.ext_key_map
+            .as_ref()
+            .is_some_and(|hm| hm.contains_key(ext))
+            || EXT_KEY_DEFAULT_TUPLES.iter().any(|(e, _)| *e == ext)
+    };
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("could not read {}", dir.display()))? {
+        let entry = entry.with_context(|| format!("could not read {}", dir.display()))?;
+        let path = entry.path();
+        if !path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(has_mapping)
+        {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        if !metadata.is_file() {
+            continue;
+        }
+        files.push((path, metadata.modified()?));
+    }
+    Ok(files)
+}
+
+/// Find the most recently modified file in `dir` whose extension maps to a language key
+pub fn find_latest_source(dir: &Path, cfg: &ConfyConfig) -> Result<PathBuf> {
+    source_files(dir, cfg)?
+        .into_iter()
+        .max_by_key(|(_, modified)| *modified)
+        .map(|(path, _)| path)
+        .with_context(|| {
+            format!(
+                "no source file with a known extension found in {}",
+                dir.display()
+            )
+        })
+}
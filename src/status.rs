@@ -0,0 +1,34 @@
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::{fs::File, io::Write, sync::Mutex};
+
+/// Machine-readable progress events, written as JSON lines to a file descriptor for frontends
+/// that show grading live (`--status-fd`)
+pub struct StatusStream {
+    file: Mutex<File>,
+}
+
+impl StatusStream {
+    /// Write events to the already open file descriptor `fd`, e.g. one set up with `3>events`
+    pub fn from_fd(fd: u32) -> Result<Self> {
+        if !cfg!(unix) {
+            return Err(anyhow!("--status-fd is only supported on Unix"));
+        }
+        let file = File::options()
+            .append(true)
+            .open(format!("/dev/fd/{}", fd))
+            .with_context(|| format!("file descriptor {} is not open for writing", fd))?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Write `event` as a single line. Failures are only logged, since the events are a
+    /// companion to the regular output.
+    pub fn emit(&self, event: Value) {
+        let mut file = self.file.lock().unwrap_or_else(|err| err.into_inner());
+        if let Err(err) = writeln!(file, "{}", event).and_then(|_| file.flush()) {
+            log::debug!("Could not write status event: {}", err);
+        }
+    }
+}
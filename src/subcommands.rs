@@ -1,13 +1,411 @@
 use crate::api::*;
+use crate::cli::{
+    LanguageSortKey, MemoryUnit, OutputFormat, ProblemType, SubmissionSortKey, SubmissionsArgs,
+};
+use crate::config::{
+    get_state, problem_code_scheme, set_state, FormFields, ProblemCodeScheme, RecentSubmission,
+    SpinnerSettings, SubmittedFile, VersionCheck,
+};
+use crate::format::{format_diff, format_memory, format_percentage, format_time, SummaryFormat};
+use crate::status::StatusStream;
+use crate::theme::{Theme, PARTIAL_AC};
+use crate::tui::LiveView;
 use anyhow::{anyhow, Context, Result};
 use console::style;
-use indicatif::ProgressBar;
-use reqwest::header::AUTHORIZATION;
-use std::sync::OnceLock;
-use std::time::{Duration, Instant};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use reqwest::header::{CONTENT_TYPE, COOKIE, REFERER};
+use reqwest::StatusCode;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::{collections::HashMap, sync::Arc};
 use APISubmissionCaseOrBatch::{Batch, Case};
 
+/// Options that affect how a submission is made and displayed
+#[derive(Clone)]
+pub struct SubmitOptions {
+    pub client: ClientOptions,
+    pub memory_unit: MemoryUnit,
+    /// Ask for confirmation if the same source was recently submitted to the same problem
+    pub guard_duplicates: bool,
+    /// Custom template for the final summary
+    pub summary_format: Option<SummaryFormat>,
+    /// Directory to save the final API response of the submission to
+    pub output_dir: Option<PathBuf>,
+    /// How long a submission can be queued before warning the user
+    pub queue_wait: Duration,
+    /// Show a desktop notification once grading finishes
+    pub notify: bool,
+    /// URL to POST the graded submission to, see `--webhook`
+    pub webhook: Option<reqwest::Url>,
+    /// Look up the submission id through the API instead of the submit redirect
+    pub id_via_api: bool,
+    /// DMOJ username, needed to look up submissions through the API
+    pub username: Option<String>,
+    /// Point out the first case that wasn't accepted once grading finishes
+    pub first_failure: bool,
+    /// Abort the submission on the judge as soon as a case isn't accepted
+    pub abort_on_fail: bool,
+    /// Kind of problem, which changes what the summary shows
+    pub problem_type: ProblemType,
+    /// Set when several submissions are graded at once, so that their spinners don't overlap.
+    /// Case lines are then prefixed with the problem code and the final summary is left to
+    /// the caller.
+    pub multi_progress: Option<MultiProgress>,
+    /// Show grading in a full-screen view instead of printing cases as they arrive
+    pub tui: bool,
+    /// Names of the fields of the submission form
+    pub form_fields: FormFields,
+    /// Print the submission request before sending it
+    pub dump_request: bool,
+    /// Show cases as a single line of glyphs, only printing failed cases in full
+    pub compact_cases: bool,
+    /// Show each batch as its own line of cases and points, see `--batch-bars`
+    pub batch_bars: bool,
+    pub show_judge: bool,
+    pub show_feedback: bool,
+    /// Number of redirects to follow after submitting while looking for the submission page
+    pub max_redirects: usize,
+    /// Show all cases in a pager once grading finishes
+    pub pager: bool,
+    /// Zip archive to upload instead of sending the source as text, for judges that accept
+    /// multi-file submissions
+    pub archive: Option<Vec<u8>>,
+    /// Ask for confirmation before submitting, see `--confirm`
+    pub confirm: bool,
+    /// Where the source comes from (e.g. its file name), shown when asking for confirmation
+    pub source_name: Option<String>,
+    /// Manifest entry to record as submitted once the submission id is known, see `--resume`
+    pub batch_entry: Option<BatchEntry>,
+    /// Print every change of the submission's top-level status, e.g. `QU -> G`
+    pub raw_status: bool,
+    /// Look of the spinner shown while grading
+    pub spinner: SpinnerSettings,
+    /// Wait until this time before submitting, see `--delay-start` and `--at`
+    pub start_at: Option<SystemTime>,
+    /// Keep a "Graded in" line instead of clearing the spinner when grading finishes
+    pub persist_progress: bool,
+    /// Where to write machine-readable progress events, see `--status-fd`
+    pub status: Option<Arc<StatusStream>>,
+    /// Minimum time between redraws of the case list, see `--min-poll-changes`
+    pub render_interval: Option<Duration>,
+    /// Styles of case statuses
+    pub theme: Theme,
+    /// Print where the time went once grading finishes, see `--timings`
+    pub timings: bool,
+    /// Judge base URL -> how the judge spells problem codes, see `problem_codes` in the
+    /// configuration
+    pub problem_codes: HashMap<String, ProblemCodeScheme>,
+}
+
+impl Default for SubmitOptions {
+    fn default() -> Self {
+        Self {
+            client: ClientOptions::default(),
+            memory_unit: MemoryUnit::Mb,
+            guard_duplicates: false,
+            summary_format: None,
+            output_dir: None,
+            queue_wait: Duration::from_secs(30),
+            notify: false,
+            webhook: None,
+            id_via_api: false,
+            username: None,
+            first_failure: false,
+            abort_on_fail: false,
+            problem_type: ProblemType::Standard,
+            multi_progress: None,
+            tui: false,
+            form_fields: FormFields::default(),
+            dump_request: false,
+            compact_cases: false,
+            batch_bars: false,
+            show_judge: false,
+            show_feedback: false,
+            max_redirects: 5,
+            pager: false,
+            archive: None,
+            status: None,
+            confirm: false,
+            source_name: None,
+            batch_entry: None,
+            raw_status: false,
+            spinner: SpinnerSettings::default(),
+            start_at: None,
+            persist_progress: false,
+            render_interval: None,
+            theme: Theme::default(),
+            timings: false,
+            problem_codes: HashMap::new(),
+        }
+    }
+}
+
+/// Submissions of the same source to the same problem within this many seconds are
+/// considered duplicates
+const DUPLICATE_WINDOW_SECS: u64 = 60;
+
+/// SHA-256 of `source`, used by the duplicate guard and `--print-hash`
+pub fn hash_source(source: &str) -> String {
+    crate::sha256::sha256_hex(source.as_bytes())
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// Check local state for an identical recent submission and ask before submitting it again.
+/// The DMOJ API does not expose submission sources, so we compare against what was
+/// recorded locally.
+fn guard_duplicate(problem: &str, source: &str) -> Result<()> {
+    let source_hash = hash_source(source);
+    let now = unix_time();
+    let duplicate = get_state()?.recent_submissions.into_iter().find(|sub| {
+        sub.problem == problem
+            && sub.source_hash == source_hash
+            && now.saturating_sub(sub.timestamp) <= DUPLICATE_WINDOW_SECS
+    });
+    if let Some(duplicate) = duplicate {
+        let prompt = format!(
+            "This file was already submitted to {} {}s ago (submission {}). Submit again?",
+            problem,
+            now.saturating_sub(duplicate.timestamp),
+            duplicate.id
+        );
+        if !crate::prompt::confirm(&prompt)? {
+            return Err(anyhow!("submission cancelled"));
+        }
+    }
+    Ok(())
+}
+
+/// Held while updating the local state, since submissions can be graded concurrently
+static STATE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Remember a submission in local state, dropping entries that are too old to matter
+fn record_submission(problem: &str, source: &str, submission_id: &str) -> Result<()> {
+    let _lock = STATE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let mut state = get_state()?;
+    let now = unix_time();
+    state
+        .recent_submissions
+        .retain(|sub| now.saturating_sub(sub.timestamp) <= DUPLICATE_WINDOW_SECS);
+    state.recent_submissions.push(RecentSubmission {
+        id: submission_id.to_string(),
+        problem: problem.to_string(),
+        source_hash: hash_source(source),
+        timestamp: now,
+    });
+    set_state(state)
+}
+
+/// Number of submitted files remembered for `resubmit`
+const SUBMITTED_FILES_LIMIT: usize = 100;
+
+/// Remember that submission `submission_id` was made from `path`
+pub fn record_submitted_file(submission_id: i32, path: &Path) -> Result<()> {
+    let path = path
+        .canonicalize()
+        .with_context(|| format!("could not resolve {}", path.display()))?;
+    let _lock = STATE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let mut state = get_state()?;
+    state.submitted_files.push(SubmittedFile {
+        id: submission_id.to_string(),
+        path,
+    });
+    let excess = state
+        .submitted_files
+        .len()
+        .saturating_sub(SUBMITTED_FILES_LIMIT);
+    state.submitted_files.drain(..excess);
+    set_state(state)
+}
+
+/// File that submission `submission_id` was made from, if it was made with dmoj-submit
+pub fn submitted_file(submission_id: &str) -> Option<PathBuf> {
+    get_state()
+        .ok()?
+        .submitted_files
+        .into_iter()
+        .rev()
+        .find(|file| file.id == submission_id)
+        .map(|file| file.path)
+}
+
+/// Entry of a manifest that is being submitted, see `SubmitOptions::batch_entry`
+#[derive(Clone)]
+pub struct BatchEntry {
+    /// Canonical path of the manifest
+    pub manifest: PathBuf,
+    pub file: PathBuf,
+}
+
+/// Path that a manifest entry's file is remembered by. Entry paths are relative to the current
+/// directory, so they are canonicalized for `--resume` to work from another directory.
+pub fn batch_entry_path(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Remember that the file of `entry` was submitted as `submission_id`
+fn record_batch_entry(entry: &BatchEntry, submission_id: &str) -> Result<()> {
+    let _lock = STATE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let mut state = get_state()?;
+    state
+        .batches
+        .entry(entry.manifest.clone())
+        .or_default()
+        .push(SubmittedFile {
+            id: submission_id.to_string(),
+            path: batch_entry_path(&entry.file),
+        });
+    set_state(state)
+}
+
+/// Entries of the manifest at `manifest` that were submitted since the batch was last started
+pub fn completed_batch_entries(manifest: &Path) -> Vec<SubmittedFile> {
+    get_state()
+        .ok()
+        .and_then(|mut state| state.batches.remove(manifest))
+        .unwrap_or_default()
+}
+
+/// Forget the progress of the batch from the manifest at `manifest`, to start it over
+pub fn clear_batch(manifest: &Path) -> Result<()> {
+    let _lock = STATE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let mut state = get_state()?;
+    if state.batches.remove(manifest).is_some() {
+        set_state(state)?;
+    }
+    Ok(())
+}
+
+/// How long the latest version found by an update check is trusted before checking again
+const VERSION_CHECK_INTERVAL_SECS: u64 = 24 * 60 * 60;
+
+/// Latest released version, looked up again if the remembered one is too old or `refresh` is
+/// set
+fn latest_version(client_options: &ClientOptions, refresh: bool) -> Result<String> {
+    let now = unix_time();
+    let cached = get_state().ok().and_then(|state| state.latest_version);
+    if let Some(check) = &cached {
+        if !refresh && now.saturating_sub(check.checked_at) < VERSION_CHECK_INTERVAL_SECS {
+            return Ok(check.version.clone());
+        }
+    }
+    let latest = get_latest_version(client_options);
+    // failed checks are remembered too, so that an unreachable crates.io doesn't slow down
+    // every run
+    let version = match &latest {
+        Ok(version) => version.clone(),
+        Err(_) => cached.map_or_else(
+            || env!("CARGO_PKG_VERSION").to_string(),
+            |check| check.version,
+        ),
+    };
+    let _lock = STATE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let mut state = get_state()?;
+    state.latest_version = Some(VersionCheck {
+        version,
+        checked_at: now,
+    });
+    set_state(state)?;
+    latest
+}
+
+/// Whether version `a` is newer than version `b`, comparing their numeric parts
+fn is_newer(a: &str, b: &str) -> bool {
+    let parts = |version: &str| {
+        version
+            .split(['.', '-', '+'])
+            .map_while(|part| part.parse::<u64>().ok())
+            .collect::<Vec<_>>()
+    };
+    parts(a) > parts(b)
+}
+
+/// Print the version of dmoj-submit and, with `check`, whether a newer one was released
+pub fn version(client_options: &ClientOptions, check: bool) -> Result<()> {
+    let current = env!("CARGO_PKG_VERSION");
+    println!("{} {}", env!("CARGO_PKG_NAME"), current);
+    if !check {
+        return Ok(());
+    }
+    let latest = latest_version(client_options, true)?;
+    println!("{} {}", style("Latest version:").bold(), latest);
+    if is_newer(&latest, current) {
+        println!(
+            "{}",
+            style("A newer version is available, update with `cargo install dmoj-submit`").yellow()
+        );
+    } else {
+        println!("dmoj-submit is up to date");
+    }
+    Ok(())
+}
+
+/// Point out a newer release on stderr, for `check_for_updates`. crates.io is asked at most
+/// once a day, and failures are ignored.
+pub fn notify_if_outdated(client_options: &ClientOptions) {
+    let current = env!("CARGO_PKG_VERSION");
+    match latest_version(client_options, false) {
+        Ok(latest) if is_newer(&latest, current) => eprintln!(
+            "{}",
+            style(format!(
+                "dmoj-submit {} is available (you have {}), update with `cargo install dmoj-submit`",
+                latest, current
+            ))
+            .yellow()
+        ),
+        Ok(_) => {}
+        Err(err) => log::debug!("Could not check for updates: {:#}", err),
+    }
+}
+
+/// Remember the details of the problem at `page`, see `State::problems`
+fn record_problem(page: &str, problem: &APIProblem) -> Result<()> {
+    let _lock = STATE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let mut state = get_state()?;
+    state.problems.insert(page.to_string(), problem.clone());
+    set_state(state)
+}
+
+/// Remember how many test cases `problem` has, for estimating grading time next time
+fn record_case_count(problem: &str, case_count: usize) -> Result<()> {
+    // compile errors and the like don't run any cases
+    if case_count == 0 {
+        return Ok(());
+    }
+    let _lock = STATE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let mut state = get_state()?;
+    state.case_counts.insert(problem.to_string(), case_count);
+    set_state(state)
+}
+
+/// Whether `url` looks like a submission page, i.e. `.../submission/<id>`
+fn is_submission_page(url: &reqwest::Url) -> bool {
+    let Some(segments) = url.path_segments() else {
+        return false;
+    };
+    let segments = segments
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    matches!(
+        segments.as_slice(),
+        [.., "submission", id] if id.bytes().all(|b| b.is_ascii_digit())
+    )
+}
+
+/// Number of times to try fetching the languages before giving up on submitting
+const LANGUAGES_FETCH_ATTEMPTS: u32 = 3;
+
+/// Number of extra polls to wait for the result once the submission status is done
+const MAX_UNSETTLED_POLLS: u32 = 3;
+
 struct FlattenedCasesItem {
     /// true if it's a case inside a batch
     is_batched_case: bool,
@@ -56,7 +454,8 @@ fn flatten_cases(cases: Vec<APISubmissionCaseOrBatch>) -> Vec<FlattenedCasesItem
 }
 
 impl FlattenedCasesItem {
-    fn gen_msg(&self) -> String {
+    fn gen_msg(&self, display: &CaseDisplay) -> String {
+        let memory_unit = display.memory_unit;
         // https://github.com/DMOJ/online-judge/blob/master/templates/submission/status-testcases.html#L51
         match &self.item {
             Case(case) => {
@@ -69,24 +468,46 @@ impl FlattenedCasesItem {
                 } else {
                     style(format!("Test case {}", padded_case_num)).bold()
                 };
+                let theme = &display.theme;
                 let status = match case.status.as_str() {
-                    "AC" if case.points == case.total => style("AC").green(),
-                    "AC" if case.points != case.total => style("AC").yellow().bright(),
-                    "WA" => style("WA").red().bright(),
-                    "TLE" => style("TLE").black(),
-                    "SC" => style("—").black(),
-                    code @ ("MLE" | "OLE" | "RTE" | "IR") => style(code).red(),
-                    unexpected_status => {
-                        log::warn!("Unexpected case status code");
-                        style(unexpected_status)
+                    "AC" if case.points != case.total => theme.apply(PARTIAL_AC, "AC"),
+                    "SC" => theme.apply("SC", "—"),
+                    code => {
+                        if !theme.is_known(code) {
+                            // judges add new status codes from time to time, so don't be noisy
+                            // about it
+                            log::debug!("Unexpected case status code `{}`", code);
+                        }
+                        theme.apply(code, code)
                     }
                 };
                 // Only used when not SC (short-circuited)
-                let time_and_mem =
-                    || format!("[{:.3}s, {:.2} MB]", case.time, case.memory / 1024.0);
+                let time_and_mem = || {
+                    let time = match display.time_limit {
+                        // TLE cases are stopped once they run out of time
+                        Some(limit) if case.status == "TLE" => format!(">{:.3}s", limit),
+                        _ => format!("{:.3}s", case.time),
+                    };
+                    match display.memory_limit {
+                        // show how far over the limit an MLE case went
+                        Some(limit) if case.status == "MLE" => format!(
+                            "[{}, {}/{}]",
+                            time,
+                            format_memory(case.memory, memory_unit),
+                            format_memory(limit, memory_unit)
+                        ),
+                        _ => format!("[{}, {}]", time, format_memory(case.memory, memory_unit)),
+                    }
+                };
                 // Only used for unbatched test cases
                 let points = || format!("({:.0}/{:.0})", case.points, case.total);
-                if case.status != "SC" {
+                let judge = match &case.judge {
+                    Some(judge) if display.show_judge => {
+                        format!(" {}", style(format!("on {}", judge)).dim())
+                    }
+                    _ => String::new(),
+                };
+                let msg = if case.status != "SC" {
                     if self.is_batched_case {
                         format!("{} {} {}", title, status, time_and_mem())
                     } else {
@@ -96,181 +517,1018 @@ impl FlattenedCasesItem {
                     format!("{} {}", title, status)
                 } else {
                     format!("{} {} {}", title, status, points())
-                }
+                };
+                msg + &judge + &display.pretest_suffix()
             }
             Batch(batch) => {
                 let title = style(format!("Batch #{}", self.num)).bold();
                 let points = format!("(?/{:.0} points)", batch.total);
-                format!("{} {}", title, points)
+                format!("{} {}{}", title, points, display.pretest_suffix())
             }
         }
     }
+
+    /// Checker feedback for a failed case, indented to go below its line. Empty if the judge
+    /// doesn't provide any.
+    fn feedback_lines(&self) -> Vec<String> {
+        let Case(case) = &self.item else {
+            return Vec::new();
+        };
+        if case.status == "AC" || case.status == "SC" {
+            return Vec::new();
+        }
+        let mut lines = Vec::new();
+        let non_empty = |text: &Option<String>| text.clone().filter(|text| !text.trim().is_empty());
+        if let Some(feedback) = non_empty(&case.feedback) {
+            lines.push(format!("{} {}", style("Feedback:").bold(), feedback.trim()));
+        }
+        if let Some(extended) = non_empty(&case.extended_feedback) {
+            lines.extend(extended.lines().map(str::to_string));
+        }
+        if let (Some(expected), Some(actual)) = (&case.expected_output, &case.output) {
+            lines.push(
+                style("Expected (-) vs. actual (+) output:")
+                    .bold()
+                    .to_string(),
+            );
+            lines.extend(format_diff(expected, actual));
+        }
+        let indent = if self.is_batched_case {
+            "      "
+        } else {
+            "    "
+        };
+        lines
+            .into_iter()
+            .map(|line| format!("{}{}", indent, line))
+            .collect()
+    }
+}
+
+/// Settings for rendering cases
+struct CaseDisplay {
+    memory_unit: MemoryUnit,
+    /// Memory limit of the problem in KB, shown for MLE cases if known
+    memory_limit: Option<f64>,
+    /// Time limit of the problem in seconds, shown for TLE cases if known
+    time_limit: Option<f64>,
+    theme: Theme,
+    /// Append the judge server that ran each case, if the judge reports it
+    show_judge: bool,
+    /// Whether the cases are pretests, so a pretest pass isn't mistaken for a full pass
+    pretested: bool,
+}
+
+impl CaseDisplay {
+    fn pretest_suffix(&self) -> String {
+        if self.pretested {
+            format!(" {}", style("(pretest)").yellow())
+        } else {
+            String::new()
+        }
+    }
+}
+
+/// One line per batch, showing its cases as glyphs as they are graded and its points, see
+/// `--batch-bars`
+struct BatchBars {
+    multi_progress: MultiProgress,
+    /// Batch number -> its line
+    bars: HashMap<i32, ProgressBar>,
 }
 
 struct Progress {
     spinner: ProgressBar,
     cases: Vec<FlattenedCasesItem>,
+    display: CaseDisplay,
+    /// Prepended to every case line, to tell concurrent submissions apart
+    label: Option<String>,
+    /// Total number of test cases, if known from an earlier grading of the problem
+    total_cases: Option<usize>,
+    /// When the first test case arrived
+    first_case_at: Option<Instant>,
+    /// Full-screen view that replaces the spinner and case lines when `--tui` is given
+    live_view: Option<LiveView>,
+    /// Messages shown below the cases in the full-screen view
+    notes: Vec<String>,
+    /// Show one glyph per case next to the spinner and only print failed cases
+    compact: bool,
+    /// Index of the last batch header printed in compact mode
+    last_printed_batch: Option<usize>,
+    /// Lines of the batches that replace their printed cases
+    batch_bars: Option<BatchBars>,
+    /// Show all cases in a pager once grading finishes instead of printing them as they arrive
+    paged: bool,
+    /// Minimum time between renders, so that cases arriving in quick succession are printed
+    /// together
+    render_interval: Option<Duration>,
+    last_render: Option<Instant>,
+    /// Indices of cases that changed since the last render
+    pending: Vec<usize>,
+    status: Option<Arc<StatusStream>>,
+    /// Leave a final line with the grading time instead of clearing the spinner
+    persist: bool,
+    /// Print checker feedback and output diffs below failed cases
+    show_feedback: bool,
+    /// When grading started to be polled
+    started: Instant,
 }
 
 impl Progress {
-    fn new() -> Self {
-        let spinner = ProgressBar::new_spinner();
-        spinner.enable_steady_tick(Duration::from_millis(120));
+    fn new(options: &SubmitOptions, total_cases: Option<usize>, label: &str) -> Self {
+        let mut spinner = ProgressBar::new_spinner();
+        let mut prefix = None;
+        let mut live_view = None;
+        if options.tui {
+            spinner = ProgressBar::hidden();
+            live_view = Some(LiveView::new(format!("Grading {}", label)));
+        } else {
+            let settings = &options.spinner;
+            let template = match (&settings.template, &options.multi_progress) {
+                (Some(template), _) => Some(template.as_str()),
+                (None, Some(_)) => Some("{spinner} {prefix:.bold} {msg}"),
+                (None, None) => None,
+            };
+            if template.is_some() || settings.tick_chars.is_some() {
+                // the settings were validated when loading the configuration
+                if let Ok(mut spinner_style) =
+                    ProgressStyle::with_template(template.unwrap_or("{spinner} {msg}"))
+                {
+                    if let Some(tick_chars) = &settings.tick_chars {
+                        spinner_style = spinner_style.tick_chars(tick_chars);
+                    }
+                    spinner.set_style(spinner_style);
+                }
+            }
+            if let Some(multi_progress) = &options.multi_progress {
+                spinner = multi_progress.add(spinner);
+                spinner.set_prefix(label.to_string());
+                prefix = Some(label.to_string());
+            }
+        }
+        // the bars can only be redrawn in a terminal, otherwise cases are printed as usual
+        let batch_bars = (options.batch_bars
+            && live_view.is_none()
+            && options.multi_progress.is_none()
+            && !spinner.is_hidden())
+        .then(|| {
+            let multi_progress = MultiProgress::new();
+            spinner = multi_progress.add(spinner.clone());
+            BatchBars {
+                multi_progress,
+                bars: HashMap::new(),
+            }
+        });
+        spinner.enable_steady_tick(options.spinner.tick_interval());
         Self {
             spinner,
             cases: Vec::new(),
+            display: CaseDisplay {
+                memory_unit: options.memory_unit,
+                memory_limit: None,
+                time_limit: None,
+                theme: options.theme.clone(),
+                show_judge: options.show_judge,
+                pretested: false,
+            },
+            label: prefix,
+            total_cases,
+            first_case_at: None,
+            live_view,
+            notes: Vec::new(),
+            compact: options.compact_cases,
+            last_printed_batch: None,
+            batch_bars,
+            paged: options.pager,
+            render_interval: options.render_interval,
+            last_render: None,
+            pending: Vec::new(),
+            status: options.status.clone(),
+            persist: options.persist_progress,
+            show_feedback: options.show_feedback,
+            started: Instant::now(),
+        }
+    }
+
+    fn println(&mut self, msg: String) {
+        if self.live_view.is_some() {
+            self.notes.push(msg);
+            self.draw();
+            return;
+        }
+        match &self.label {
+            Some(label) => {
+                self.spinner
+                    .println(format!("{} {}", style(format!("[{}]", label)).dim(), msg))
+            }
+            None => self.spinner.println(msg),
         }
     }
 
+    /// Whether any case reported the judge server that ran it
+    fn has_judge_info(&self) -> bool {
+        self.cases
+            .iter()
+            .any(|case| matches!(&case.item, Case(c) if c.judge.is_some()))
+    }
+
+    /// Number of test cases received so far, not counting batch headers
+    fn case_count(&self) -> usize {
+        self.cases
+            .iter()
+            .filter(|case| matches!(case.item, Case(_)))
+            .count()
+    }
+
+    /// Estimated time remaining, based on the rate at which cases have been arriving so far
+    fn eta(&self) -> Option<String> {
+        let (Some(total), Some(first_case_at)) = (self.total_cases, self.first_case_at) else {
+            return None;
+        };
+        let done = self.case_count();
+        // the rate is measured from the first case on, so it needs at least two cases
+        if done < 2 || done >= total {
+            return None;
+        }
+        let per_case = first_case_at.elapsed().as_secs_f64() / (done - 1) as f64;
+        let remaining = per_case * (total - done) as f64;
+        Some(format!(
+            "{}/{} cases, about {:.0}s remaining",
+            done,
+            total,
+            remaining.ceil()
+        ))
+    }
+
+    /// One glyph per case, e.g. `✓✓✗·`
+    fn glyphs(&self) -> String {
+        self.cases
+            .iter()
+            .filter_map(|case| match &case.item {
+                Case(c) => Some(match c.status.as_str() {
+                    "AC" if c.points == c.total => self.display.theme.apply("AC", "✓"),
+                    "AC" => self.display.theme.apply(PARTIAL_AC, "✓"),
+                    "SC" => self.display.theme.apply("SC", "·"),
+                    status => self.display.theme.apply(status, "✗"),
+                }),
+                Batch(_) => None,
+            })
+            .map(|glyph| glyph.to_string())
+            .collect()
+    }
+
+    /// Update the text next to the spinner
+    fn update_message(&self) {
+        let mut parts = Vec::new();
+        if self.compact {
+            parts.push(self.glyphs());
+        }
+        parts.extend(self.eta());
+        self.spinner.set_message(parts.join(" "));
+    }
+
     fn extend(&mut self, cases: Vec<APISubmissionCaseOrBatch>) {
-        let mut cases = flatten_cases(cases);
+        // print cases that are new or that changed since the last poll (e.g. a case that was
+        // still being graded), and keep self.cases up to date
+        for (idx, case) in flatten_cases(cases).into_iter().enumerate() {
+            let msg = case.gen_msg(&self.display);
+            match self.cases.get_mut(idx) {
+                Some(old_case) if old_case.gen_msg(&self.display) == msg => {}
+                Some(old_case) => {
+                    *old_case = case;
+                    if !self.pending.contains(&idx) {
+                        self.pending.push(idx);
+                    }
+                    self.emit_case(idx, "case_changed");
+                }
+                None => {
+                    if self.first_case_at.is_none() && matches!(case.item, Case(_)) {
+                        self.first_case_at = Some(Instant::now());
+                    }
+                    self.cases.push(case);
+                    self.pending.push(idx);
+                    self.emit_case(idx, "case");
+                }
+            }
+        }
+        self.render_if_due();
+    }
 
-        let new_cases = cases.split_off(self.cases.len());
-        let _old_cases = cases;
+    /// Write a `--status-fd` event for the case at `idx`. Batch headers carry no results, so
+    /// they don't get events of their own.
+    fn emit_case(&self, idx: usize, event: &str) {
+        let Some(status) = &self.status else {
+            return;
+        };
+        let case = &self.cases[idx];
+        let Case(c) = &case.item else {
+            return;
+        };
+        let batch = if case.is_batched_case {
+            self.cases[..idx]
+                .iter()
+                .rfind(|case| matches!(case.item, Batch(_)))
+                .map(|batch| batch.num)
+        } else {
+            None
+        };
+        status.emit(json!({
+            "event": event,
+            "batch": batch,
+            "case": case.num,
+            "status": c.status,
+            "time": c.time,
+            "memory": c.memory,
+            "points": c.points,
+            "total": c.total,
+        }));
+    }
 
-        // print new cases and add to self.cases
-        for case in new_cases.into_iter() {
-            self.spinner.println(case.gen_msg());
-            self.cases.push(case);
+    /// Render unless the last render was less than `render_interval` ago
+    fn render_if_due(&mut self) {
+        let due = match (self.render_interval, self.last_render) {
+            (Some(interval), Some(last_render)) => last_render.elapsed() >= interval,
+            _ => true,
+        };
+        if due {
+            self.render();
         }
     }
 
-    fn finish(self) {
-        self.spinner.finish_and_clear();
+    /// Print the cases that changed since the last render and refresh the spinner or view
+    fn render(&mut self) {
+        let mut pending = std::mem::take(&mut self.pending);
+        pending.sort_unstable();
+        for idx in pending {
+            self.print_case(idx);
+        }
+        self.update_message();
+        self.update_batch_bars(false);
+        self.draw();
+        self.last_render = Some(Instant::now());
+    }
+
+    /// Refresh the line of every batch, adding lines for new batches. A batch's points are only
+    /// shown once it is done, i.e. once a later case arrived, a case failed, or grading
+    /// `finished`.
+    fn update_batch_bars(&mut self, finished: bool) {
+        let Some(batch_bars) = &mut self.batch_bars else {
+            return;
+        };
+        let theme = &self.display.theme;
+        for (idx, header) in self.cases.iter().enumerate() {
+            let Batch(batch) = &header.item else {
+                continue;
+            };
+            let cases = self.cases[idx + 1..]
+                .iter()
+                .take_while(|case| case.is_batched_case)
+                .filter_map(|case| match &case.item {
+                    Case(c) => Some(c),
+                    Batch(_) => None,
+                })
+                .collect::<Vec<_>>();
+            let glyphs = cases
+                .iter()
+                .map(|c| match c.status.as_str() {
+                    "AC" => theme.apply("AC", "✓"),
+                    "SC" => theme.apply("SC", "·"),
+                    status => theme.apply(status, "✗"),
+                })
+                .map(|glyph| glyph.to_string())
+                .collect::<String>();
+            let failed = cases.iter().any(|c| c.status != "AC" && c.status != "SC");
+            let done = finished || failed || idx + 1 + cases.len() < self.cases.len();
+            let points = if done {
+                format!("{:.0}/{:.0}", batch.points, batch.total)
+            } else {
+                format!("?/{:.0}", batch.total)
+            };
+            let bar = batch_bars.bars.entry(header.num).or_insert_with(|| {
+                let bar = ProgressBar::new_spinner();
+                if let Ok(bar_style) = ProgressStyle::with_template("{prefix:.bold} {msg}") {
+                    bar.set_style(bar_style);
+                }
+                bar.set_prefix(format!("Batch #{}", header.num));
+                // batches stay in order above the spinner
+                batch_bars.multi_progress.insert_before(&self.spinner, bar)
+            });
+            bar.set_message(format!(
+                "{} {} points{}",
+                glyphs,
+                points,
+                self.display.pretest_suffix()
+            ));
+            if finished {
+                bar.finish();
+            }
+        }
+    }
+
+    fn print_case(&mut self, idx: usize) {
+        // the full-screen view shows all cases on every redraw, and paged cases are shown at
+        // the end
+        if self.live_view.is_some() || self.paged {
+            return;
+        }
+        let case = &self.cases[idx];
+        // batches are shown by their bars, only their feedback is printed
+        if self.batch_bars.is_some() && (case.is_batched_case || matches!(case.item, Batch(_))) {
+            if self.show_feedback {
+                for line in case.feedback_lines() {
+                    self.println(line);
+                }
+            }
+            return;
+        }
+        if self.compact {
+            let failed = matches!(&case.item, Case(c) if c.status != "AC" && c.status != "SC");
+            if !failed {
+                return;
+            }
+            // keep the batch header of a failed case, so it's clear where the case belongs
+            if case.is_batched_case {
+                let header = self.cases[..idx]
+                    .iter()
+                    .rposition(|case| matches!(case.item, Batch(_)));
+                if let Some(header_idx) = header.filter(|_| header != self.last_printed_batch) {
+                    self.last_printed_batch = header;
+                    let msg = self.cases[header_idx].gen_msg(&self.display);
+                    self.println(msg);
+                }
+            }
+        }
+        let msg = self.cases[idx].gen_msg(&self.display);
+        self.println(msg);
+        if self.show_feedback {
+            for line in self.cases[idx].feedback_lines() {
+                self.println(line);
+            }
+        }
+    }
+
+    /// Redraw the full-screen view, if there is one
+    fn draw(&self) {
+        let Some(live_view) = &self.live_view else {
+            return;
+        };
+        let lines = self
+            .cases
+            .iter()
+            .map(|case| case.gen_msg(&self.display))
+            .chain(self.notes.iter().cloned())
+            .collect::<Vec<_>>();
+        let status = if self.cases.is_empty() {
+            "Waiting for grading to start".to_string()
+        } else {
+            // batched cases are already counted by their batch
+            let (points, total) = self.cases.iter().filter(|case| !case.is_batched_case).fold(
+                (0.0, 0.0),
+                |(points, total), case| match &case.item {
+                    Case(c) => (points + c.points, total + c.total),
+                    Batch(b) => (points + b.points, total + b.total),
+                },
+            );
+            format!("Score so far: {:.0}/{:.0}", points, total)
+        };
+        if let Err(err) = live_view.draw(&lines, &status) {
+            log::debug!("Could not draw the live view: {}", err);
+        }
+    }
+
+    /// Describe the first case that wasn't accepted, e.g. "Batch #3, Case #2 (WA)"
+    fn first_failure(&self) -> Option<String> {
+        let mut batch_num = None;
+        for case in &self.cases {
+            match &case.item {
+                Batch(_) => batch_num = Some(case.num),
+                Case(c) => {
+                    if !case.is_batched_case {
+                        batch_num = None;
+                    }
+                    // short-circuited cases are skipped because of an earlier failure
+                    if c.status == "AC" || c.status == "SC" {
+                        continue;
+                    }
+                    return Some(match batch_num {
+                        Some(batch_num) => {
+                            format!("Batch #{}, Case #{} ({})", batch_num, case.num, c.status)
+                        }
+                        None => format!("Test case #{} ({})", case.num, c.status),
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// Render cases that are still waiting for the next render
+    fn flush(&mut self) {
+        if !self.pending.is_empty() {
+            self.render();
+        }
+    }
+
+    fn finish(mut self) {
+        self.flush();
+        self.update_batch_bars(true);
+        if self.compact && self.live_view.is_none() {
+            let glyphs = self.glyphs();
+            self.println(glyphs);
+        }
+        if self.persist {
+            let msg = format!("Graded in {:.1}s", self.started.elapsed().as_secs_f64());
+            let msg = match &self.label {
+                Some(label) => format!("{} {}", style(format!("[{}]", label)).dim(), msg),
+                None => msg,
+            };
+            // hidden spinners (e.g. when output isn't a terminal) don't draw their final message
+            if self.spinner.is_hidden() && self.live_view.is_none() {
+                println!("{}", msg);
+            }
+            self.spinner.finish_with_message(msg);
+        } else {
+            self.spinner.finish_and_clear();
+        }
+        if self.paged {
+            let text = self
+                .cases
+                .iter()
+                .flat_map(|case| {
+                    let feedback = if self.show_feedback {
+                        case.feedback_lines()
+                    } else {
+                        Vec::new()
+                    };
+                    std::iter::once(case.gen_msg(&self.display)).chain(feedback)
+                })
+                .map(|line| line + "\n")
+                .collect::<String>();
+            if let Err(err) = page(&text) {
+                log::warn!("Could not show cases in a pager: {:#}", err);
+                print!("{}", text);
+            }
+        }
     }
 }
 
-pub fn submit(problem: &str, source: &str, token: &str, language: &str) -> Result<()> {
-    // make a map of language keys to language ids
-    let key_id_map = get_languages()?
-        .into_iter()
+/// Id of the language with key `language`, ignoring case
+fn language_id(languages: &[APILanguage], language: &str) -> Result<i32> {
+    let key_id_map = languages
+        .iter()
         .map(|lang| (lang.key.to_lowercase(), lang.id))
         .collect::<HashMap<String, i32>>();
-    let lang_id = key_id_map
+    key_id_map
         .get(&language.to_lowercase())
-        .with_context(|| "could not determine language id")?;
-
-    let header = format!("Bearer {}", token);
-    let url = format!("{}/problem/{}/submit", BASE_URL, problem);
-    let params = [
-        ("problem", problem),
-        ("source", source),
-        ("language", &lang_id.to_string()),
-    ];
+        .copied()
+        .with_context(|| {
+            let mut valid_keys = key_id_map.keys().cloned().collect::<Vec<String>>();
+            valid_keys.sort_unstable();
+            format!(
+                "language key `{}` does not exist, valid keys are: {}",
+                language,
+                valid_keys.join(", ")
+            )
+        })
+}
+
+/// Build the request that submits `source` to `problem`, with the form fields and
+/// authentication the judge expects
+fn submit_request(
+    client: &reqwest::blocking::Client,
+    problem: &str,
+    lang_id: i32,
+    source: &str,
+    auth: &Auth,
+    options: &SubmitOptions,
+) -> Result<reqwest::blocking::RequestBuilder> {
+    let url = options.client.endpoints.submit_page(problem);
+    let lang_id = lang_id.to_string();
+    let fields = &options.form_fields;
+    let mut params = vec![(fields.problem(), problem), (fields.language(), &lang_id)];
+    // archives are uploaded as a file instead
+    if options.archive.is_none() {
+        params.push((fields.source(), source));
+    }
+    // API tokens are exempt from CSRF protection, but session cookies aren't
+    let csrf_token = match auth {
+        Auth::SessionCookie(_) => Some(get_csrf_token(&options.client, auth, &url)?),
+        Auth::Token(_) => None,
+    };
+    if let Some(csrf_token) = &csrf_token {
+        params.push(("csrfmiddlewaretoken", csrf_token));
+    }
+    log::info!("Fetching {} ...", url);
+    let request = match (auth, &csrf_token) {
+        (Auth::SessionCookie(cookie), Some(csrf_token)) => client
+            .post(&url)
+            .header(
+                COOKIE,
+                format!("sessionid={}; csrftoken={}", cookie, csrf_token),
+            )
+            .header(REFERER, &url),
+        _ => auth.apply(client.post(&url)),
+    };
+    Ok(match &options.archive {
+        Some(archive) => {
+            let (content_type, body) = multipart_form(
+                &params,
+                fields.source_file(),
+                &format!("{}.zip", problem),
+                archive,
+            );
+            request.header(CONTENT_TYPE, content_type).body(body)
+        }
+        None => request.form(&params),
+    })
+}
+
+/// Print the request that `submit` would send, without sending it, for `--dump-request` with
+/// `--dry-run`
+pub fn dump_submit_request(
+    problem: &str,
+    source: &str,
+    auth: &Auth,
+    language: &str,
+    options: &SubmitOptions,
+) -> Result<()> {
+    let scheme = problem_code_scheme(&options.problem_codes, options.client.endpoints.judge_url());
+    let problem = scheme.normalize(problem);
+    let languages = with_retries(LANGUAGES_FETCH_ATTEMPTS, || get_languages(&options.client))?;
+    let lang_id = language_id(&languages, language)?;
+    let client = options.client.client()?;
+    dump_request(&submit_request(
+        &client, &problem, lang_id, source, auth, options,
+    )?)
+}
+
+pub fn submit(
+    problem: &str,
+    source: &str,
+    auth: &Auth,
+    language: &str,
+    options: &SubmitOptions,
+) -> Result<APISubmission> {
+    let scheme = problem_code_scheme(&options.problem_codes, options.client.endpoints.judge_url());
+    let problem = scheme.normalize(problem);
+    let problem = problem.as_str();
+    let started = Instant::now();
+    // make a map of language keys to language ids
+    // nothing has been submitted yet, so it's safe to retry this
+    let languages = with_retries(LANGUAGES_FETCH_ATTEMPTS, || get_languages(&options.client))?;
+    let languages_time = started.elapsed();
+    let lang_id = language_id(&languages, language)?;
+    // Problems may restrict the languages that can be used, so check that before submitting
+    let problem_page = options.client.endpoints.problem_page(problem);
+    let problem_info = match get_problem(&options.client, problem, auth) {
+        Ok(problem_info) => {
+            if let Err(err) = record_problem(&problem_page, &problem_info) {
+                log::warn!("Could not record problem in local state: {:#}", err);
+            }
+            let allowed_keys = problem_info
+                .languages
+                .iter()
+                .map(|key| key.to_lowercase())
+                .collect::<Vec<String>>();
+            if !allowed_keys.contains(&language.to_lowercase()) {
+                return Err(anyhow!(
+                    "language key `{}` is not allowed for problem {}, valid keys are: {}",
+                    language,
+                    problem,
+                    allowed_keys.join(", ")
+                ));
+            }
+            Some(problem_info)
+        }
+        Err(err) => {
+            log::warn!(
+                "Could not fetch problem info, skipping allowed language check: {:#}",
+                err
+            );
+            // the name and limits from last time are still good for displaying
+            get_state()
+                .ok()
+                .and_then(|mut state| state.problems.remove(&problem_page))
+        }
+    };
+    if options.multi_progress.is_none() {
+        if let Some(problem_info) = &problem_info {
+            println!(
+                "{} {} ({})",
+                style("Submitting to").bold(),
+                problem_info.name,
+                problem
+            );
+        }
+    }
+
+    if options.guard_duplicates {
+        guard_duplicate(problem, source)?;
+    }
+    // waiting for the user or the scheduled time doesn't count towards --timings
+    let pause_start = Instant::now();
+    if options.confirm {
+        confirm_submission(problem, language, options)?;
+    }
+    if let Some(start_at) = options.start_at {
+        wait_until(start_at);
+    }
+    let paused = pause_start.elapsed();
+
+    let submit_start = Instant::now();
     // Need some concurrency primitives here to appease the compiler
     let redirect_url = Arc::new(OnceLock::new());
+    // The same client is used for submitting and polling, so the polls reuse the pooled
+    // connection of the submit request instead of starting with another TCP and TLS handshake.
+    // Looking for the submission page only applies to the submit request itself.
+    let submitting = Arc::new(AtomicBool::new(true));
     let client = {
         let redirect_url_clone = Arc::clone(&redirect_url);
-        reqwest::blocking::Client::builder()
+        let submitting_clone = Arc::clone(&submitting);
+        let max_redirects = options.max_redirects;
+        options
+            .client
+            .builder()?
             .redirect(reqwest::redirect::Policy::custom(move |attempt| {
-                redirect_url_clone.get_or_init(|| attempt.url().clone());
-                attempt.stop()
+                if !submitting_clone.load(Ordering::Relaxed) {
+                    // same limit as reqwest's default policy
+                    return if attempt.previous().len() < 10 {
+                        attempt.follow()
+                    } else {
+                        attempt.error("too many redirects")
+                    };
+                }
+                // some judges redirect somewhere else before the submission page, so follow
+                // redirects until we reach it, without actually loading it
+                if is_submission_page(attempt.url()) {
+                    redirect_url_clone.get_or_init(|| attempt.url().clone());
+                    attempt.stop()
+                } else if attempt.previous().len() <= max_redirects {
+                    log::debug!("Following redirect to {}", attempt.url());
+                    attempt.follow()
+                } else {
+                    attempt.stop()
+                }
             }))
             .build()
     }?;
-    log::info!("Fetching {} ...", url);
-    let submission = client
-        .post(&url)
-        .form(&params)
-        .header(AUTHORIZATION, &header)
-        .send()?;
-
-    let redirect_url = redirect_url
-        .get()
-        .with_context(|| "Submission request did not get redirected to the submission page")?;
+    let request = submit_request(&client, problem, lang_id, source, auth, options)?;
+    if options.dump_request {
+        dump_request(&request)?;
+    }
+    let submission = options.client.send(request)?;
+    submitting.store(false, Ordering::Relaxed);
+
     let res = submission.status().as_u16();
+    // the redirects may end on a page other than the submission page (e.g. an interstitial),
+    // in which case the submission id is looked up through the API below
+    let landed = (200..300).contains(&res) && options.archive.is_none();
+    if landed {
+        log::debug!("Submission request ended on {}", submission.url());
+        if is_submission_page(submission.url()) {
+            redirect_url.get_or_init(|| submission.url().clone());
+        }
+    }
     // TODO: figure out wonkiness with POST codes to make sure it does not break the below code block
-    if res != 302 {
+    if !(300..400).contains(&res) && !landed {
         return match res {
+            // the judge shows the form again with an error if it can't handle the upload
+            200 if options.archive.is_some() => Err(anyhow!(
+                "the judge did not accept the archive; submitting archives requires a judge that accepts file uploads for language `{}`",
+                language
+            )),
             400 => Err(anyhow!(
                 "Error 400, bad request, the header you provided is invalid"
             )),
             401 => Err(anyhow!(
-                "Error 401, unauthorized, the token you provided is invalid"
+                "Error 401, unauthorized, the {} you provided is invalid",
+                auth.describe()
             )),
             403 => Err(anyhow!(
                 "Error 403, forbidden, you are trying to access the admin portion of the site"
             )),
-            404 => Err(anyhow!("Error 404, not found, the problem does not exist")),
+            404 => Err(match suggest_problem_code(&options.client, problem) {
+                Some(code) => anyhow!(
+                    "Error 404, not found, the problem does not exist; did you mean `{}`? Problem codes are case-sensitive",
+                    code
+                ),
+                None => anyhow!(
+                    "Error 404, not found, the problem does not exist; problem codes are case-sensitive"
+                ),
+            }),
             500 => Err(anyhow!("Error 500, internal server error")),
             code => Err(anyhow!("Code {}, unknown network error", code)),
         };
     }
-    log::info!("submission url: {}", redirect_url);
-    let submission_id = redirect_url
-        .as_str()
-        .split('/')
-        .last()
-        .with_context(|| "could not determine submission id")?;
+    // read the rest of the (short) redirect response, otherwise its connection is closed
+    // instead of going back to the pool for polling
+    if let Err(err) = submission.bytes() {
+        log::debug!("Could not read submit response: {}", err);
+    }
+    let redirect_id = redirect_url.get().and_then(|redirect_url| {
+        log::info!("submission url: {}", redirect_url);
+        redirect_url
+            .path_segments()?
+            .next_back()
+            .map(str::to_string)
+    });
+    let submission_id = match redirect_id {
+        Some(id) if !options.id_via_api => id,
+        redirect_id => {
+            if redirect_id.is_none() {
+                log::info!("Submission request did not get redirected to the submission page, looking up the submission id through the API");
+            }
+            let username = options.username.as_deref().with_context(|| {
+                "looking up the submission id through the API requires a username, set it using `dmoj-submit set-config --username ...`"
+            })?;
+            get_latest_submission_id(&options.client, auth, username, problem)?
+                .with_context(|| "could not determine submission id")?
+                .to_string()
+        }
+    };
     log::info!("submission id: {}", submission_id);
+    if let Some(status) = &options.status {
+        status.emit(json!({
+            "event": "submitted",
+            "problem": problem,
+            "submission_id": submission_id,
+        }));
+    }
+    if let Err(err) = record_submission(problem, source, &submission_id) {
+        log::warn!("Could not record submission in local state: {:#}", err);
+    }
+    // recorded before polling, so that a failure while grading doesn't get the entry submitted
+    // again on resume
+    if let Some(entry) = &options.batch_entry {
+        if let Err(err) = record_batch_entry(entry, &submission_id) {
+            log::warn!("Could not record batch progress in local state: {:#}", err);
+        }
+    }
 
-    let client = reqwest::blocking::Client::new();
-    let mut progress = Progress::new();
+    let total_cases = get_state()
+        .ok()
+        .and_then(|state| state.case_counts.get(problem).copied());
+    let mut progress = Progress::new(options, total_cases, problem);
+    progress.display.memory_limit = problem_info.as_ref().map(|info| info.memory_limit);
+    progress.display.time_limit = problem_info.as_ref().map(|info| info.time_limit);
+    let poll_start = Instant::now();
+    let mut queue_warned = false;
+    let mut validator: Option<Validator> = None;
+    let mut last_status: Option<String> = None;
+    // polls made after the status reached done but before the result was filled in
+    let mut unsettled_polls = 0;
+    let mut aborted = false;
     loop {
         let before_req = Instant::now();
-        // TODO: add more logging
-        let json: APIResponse<APISingleData<APISubmission>> = client
-            .get(format!("{}/api/v2/submission/{}", BASE_URL, submission_id))
-            .header(AUTHORIZATION, &header)
-            .send()?
+        // warn once if the submission is stuck in the queue, i.e. grading hasn't started
+        if !queue_warned && progress.cases.is_empty() && poll_start.elapsed() >= options.queue_wait
+        {
+            queue_warned = true;
+            progress.println(
+                style(format!(
+                    "Still queued after {}s, the judge may be busy",
+                    options.queue_wait.as_secs()
+                ))
+                .yellow()
+                .to_string(),
+            );
+        }
+        let mut request =
+            auth.apply(client.get(options.client.endpoints.submission(&submission_id)));
+        if let Some(validator) = &validator {
+            request = validator.apply(request);
+        }
+        let response = options.client.send(request)?;
+        // nothing changed since the last poll, so there's nothing to update
+        if response.status() == StatusCode::NOT_MODIFIED {
+            log::debug!("Submission {} unchanged since last poll", submission_id);
+            // cases held back by --min-poll-changes may be due by now
+            progress.render_if_due();
+            std::thread::sleep(Duration::from_secs(1).saturating_sub(before_req.elapsed()));
+            continue;
+        }
+        // judges that don't support conditional requests simply won't send a validator, in
+        // which case every poll fetches the full submission
+        validator = Validator::from_headers(response.headers());
+        let json: APIResponse<APISingleData<APISubmission>> = response
             .json()
             .with_context(|| "converting API response to json failed")?;
-        // TODO: maybe add a dmoj_json_unwrap function that encapsulates the
-        // if let Some(error) = json.error ... else if let Some(data) = json.data ... else return err
-        // form and just returns a Result with successful data.
-        // Right now this form is copied/repeated in get_languages.
-        if let Some(error) = json.error {
-            return Err(anyhow!(
-                "API request failed with code {} and message `{}`",
-                error.code,
-                error.message
-            ));
-        } else if let Some(data) = json.data {
-            progress.extend(data.object.cases);
-
-            if let Some(result) = data.object.result {
-                // Submission has finished grading
-                progress.finish();
-                println!();
-                // https://github.com/DMOJ/online-judge/blob/master/templates/submission/status-testcases.html#L126
-                match result.as_str() {
-                    "IE" => {
-                        // https://github.com/DMOJ/online-judge/blob/master/templates/submission/internal-error-message.html#L3
-                        println!("{}", style("An internal error occurred while grading, and the DMOJ administrators have been notified\nIn the meantime, try resubmitting in a few seconds.").red().bright())
-                    }
-                    "CE" => println!("Compilation error"),
-                    "AB" => println!("Submission aborted!"),
-                    _ => {
-                        // print resources
-                        println!(
-                            "{} {}, {:.2} MB",
-                            style("Resources:").bold(),
-                            if result == "TLE" {
-                                "---".to_string()
-                            } else {
-                                format!("{:.3}s", data.object.time.unwrap())
-                            },
-                            data.object.memory.unwrap() / 1024.0,
-                        );
-
-                        // TODO: implement maximum single-case runtime
-
-                        // print final score
-                        println!(
-                            "{} {:.0}/{:.0}",
-                            style("Final score:").bold(),
-                            data.object.case_points,
-                            data.object.case_total
-                        );
-                    }
+        if let Some(output_dir) = &options.output_dir {
+            let finished = json
+                .data
+                .as_ref()
+                .is_some_and(|data| data.object.is_finished());
+            if finished {
+                if let Err(err) = save_response(output_dir, &submission_id, &json) {
+                    log::error!("Could not save submission: {:#}", err);
                 }
-                break;
             }
-        } else {
+        }
+        // the token can expire or be revoked while we're still polling; the submission itself
+        // is unaffected, so point the user to the website instead of reporting a generic error
+        if json.error.as_ref().is_some_and(|error| error.code == 401) {
+            progress.finish();
             return Err(anyhow!(
-                "Neither data nor error were defined in the API response"
+                "{} became invalid during grading; submission {} is still being graded, check {}",
+                auth.describe(),
+                submission_id,
+                options.client.endpoints.submission_page(&submission_id)
             ));
         }
+        let mut data = unwrap_response(json)?;
+        if options.raw_status && last_status.as_deref() != Some(data.object.status.as_str()) {
+            progress.println(
+                style(format!(
+                    "[{:>6.1}s] status {} -> {}",
+                    poll_start.elapsed().as_secs_f64(),
+                    last_status.as_deref().unwrap_or("(none)"),
+                    data.object.status
+                ))
+                .dim()
+                .to_string(),
+            );
+            last_status = Some(data.object.status.clone());
+        }
+        progress.display.pretested = data.object.is_pretested;
+        progress.extend(data.object.cases.clone());
+
+        if options.abort_on_fail && !aborted && !data.object.is_finished() {
+            if let Some(first_failure) = progress.first_failure() {
+                // only try once, the judge reports the abort through the status when it's done
+                aborted = true;
+                match abort_submission(&options.client, &client, auth, &submission_id) {
+                    Ok(()) => progress.println(
+                        style(format!("Aborting after {}", first_failure))
+                            .yellow()
+                            .to_string(),
+                    ),
+                    Err(err) => log::warn!("Could not abort submission: {:#}", err),
+                }
+            }
+        }
+        if data.object.is_finished() {
+            // Submission has finished grading
+            if data.object.result.is_none() {
+                // the status is done but the result isn't filled in yet, which should only take
+                // another poll or two
+                if data.object.status == "D" && unsettled_polls < MAX_UNSETTLED_POLLS {
+                    unsettled_polls += 1;
+                    log::debug!("Submission {} done but has no result yet", submission_id);
+                    std::thread::sleep(Duration::from_secs(1).saturating_sub(before_req.elapsed()));
+                    continue;
+                }
+                // compile errors, internal errors, and aborts are their own verdict; for a
+                // result that never shows up, the status is the best we have
+                data.object.result = Some(data.object.status.clone());
+            }
+            if let Err(err) = record_case_count(problem, progress.case_count()) {
+                log::warn!("Could not record case count in local state: {:#}", err);
+            }
+            let timings = Timings {
+                languages: languages_time,
+                submit: poll_start.duration_since(submit_start),
+                queue: progress
+                    .first_case_at
+                    .map(|first_case_at| first_case_at.duration_since(poll_start)),
+                grading: progress.first_case_at.unwrap_or(poll_start).elapsed(),
+                total: started.elapsed().saturating_sub(paused),
+            };
+            // the judge may record a different language than the key we sent, e.g. for aliases
+            let language_name = languages
+                .iter()
+                .find(|lang| lang.key.eq_ignore_ascii_case(&data.object.language))
+                .map(|lang| lang.short_name.as_ref().unwrap_or(&lang.common_name))
+                .unwrap_or(&data.object.language);
+            show_result(progress, &data.object, language_name, options);
+            if let Some(status) = &options.status {
+                status.emit(json!({ "event": "result", "submission": data.object }));
+            }
+            log::info!(
+                "Submitted {} ({})",
+                crate::date::format_local(&data.object.date),
+                crate::date::format_relative(&data.object.date)
+            );
+            if options.timings && options.multi_progress.is_none() {
+                timings.print();
+            }
+            if options.notify {
+                let verdict = data.object.result.as_deref().unwrap_or_default();
+                let body = format!(
+                    "{} ({:.0}/{:.0})",
+                    verdict, data.object.case_points, data.object.case_total
+                );
+                if let Err(err) = notify(&format!("{} graded", problem), &body) {
+                    log::warn!("Could not show desktop notification: {:#}", err);
+                }
+            }
+            if let Some(url) = &options.webhook {
+                // the submission went through either way, so a failing hook isn't fatal
+                if let Err(err) = post_webhook(options, &client, url, &data.object) {
+                    log::warn!("Could not send submission to webhook: {:#}", err);
+                }
+            }
+            return Ok(data.object);
+        }
         let after_req = Instant::now();
         // 1 second between requests
         // We can subtract the time that the request took
@@ -278,15 +1536,526 @@ pub fn submit(problem: &str, source: &str, token: &str, language: &str) -> Resul
             Duration::from_secs(1).saturating_sub(after_req.duration_since(before_req)),
         );
     }
+}
+
+/// Finish displaying the cases of the graded `submission` and print its summary, unless
+/// several submissions are graded at once
+fn show_result(
+    mut progress: Progress,
+    submission: &APISubmission,
+    language_name: &str,
+    options: &SubmitOptions,
+) {
+    progress.flush();
+    if options.show_judge && !progress.has_judge_info() {
+        progress.println(
+            style("The judge did not report which judge server ran each case")
+                .yellow()
+                .to_string(),
+        );
+    }
+    if options.first_failure {
+        if let Some(first_failure) = progress.first_failure() {
+            progress.println(
+                style(format!("First failure: {}", first_failure))
+                    .red()
+                    .bold()
+                    .to_string(),
+            );
+        }
+    }
+    progress.finish();
+    if options.multi_progress.is_none() {
+        print_summary(submission, language_name, options);
+    }
+}
+
+/// Show a submission saved with `--output-dir` as if it was being graded, without contacting
+/// the judge
+pub fn replay(path: &Path, options: &SubmitOptions) -> Result<()> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("could not read {}", path.display()))?;
+    // files saved with --output-dir contain the whole API response, but a bare submission
+    // object is fine too
+    let submission =
+        match serde_json::from_str::<APIResponse<APISingleData<APISubmission>>>(&contents) {
+            Ok(response) => unwrap_response(response)?.object,
+            Err(_) => serde_json::from_str::<APISubmission>(&contents)
+                .with_context(|| format!("{} does not contain a submission", path.display()))?,
+        };
+    let mut progress = Progress::new(options, None, &submission.problem);
+    progress.display.pretested = submission.is_pretested;
+    progress.extend(submission.cases.clone());
+    show_result(progress, &submission, &submission.language, options);
+    Ok(())
+}
+
+/// Where the time of a submission went, see `--timings`
+struct Timings {
+    languages: Duration,
+    /// Submitting, up to knowing the submission id
+    submit: Duration,
+    /// Waiting for the first case, if there were any cases
+    queue: Option<Duration>,
+    grading: Duration,
+    /// Everything except waiting for confirmation or a scheduled time
+    total: Duration,
+}
+
+impl Timings {
+    fn print(&self) {
+        println!();
+        println!("{}", style("Timings:").bold());
+        let queue = self
+            .queue
+            .map(|queue| format!("{:.3}s", queue.as_secs_f64()))
+            .unwrap_or_else(|| "---".to_string());
+        for (label, value) in [
+            (
+                "Languages fetch",
+                format!("{:.3}s", self.languages.as_secs_f64()),
+            ),
+            ("Submit", format!("{:.3}s", self.submit.as_secs_f64())),
+            ("Queue", queue),
+            ("Grading", format!("{:.3}s", self.grading.as_secs_f64())),
+            ("Total", format!("{:.3}s", self.total.as_secs_f64())),
+        ] {
+            println!("  {:<16} {:>9}", label, value);
+        }
+    }
+}
+
+/// Sleep until `start_at` while counting down next to a spinner. Ctrl-C exits before anything is
+/// submitted.
+fn wait_until(start_at: SystemTime) {
+    let spinner = ProgressBar::new_spinner();
+    spinner.enable_steady_tick(Duration::from_millis(120));
+    while let Ok(remaining) = start_at.duration_since(SystemTime::now()) {
+        let secs = remaining.as_secs();
+        spinner.set_message(format!(
+            "Submitting in {}:{:02}:{:02}",
+            secs / 3600,
+            secs % 3600 / 60,
+            secs % 60
+        ));
+        std::thread::sleep(remaining.min(Duration::from_millis(200)));
+    }
+    spinner.finish_and_clear();
+}
+
+/// Show what is about to be submitted and ask whether to go ahead
+fn confirm_submission(problem: &str, language: &str, options: &SubmitOptions) -> Result<()> {
+    if !crate::prompt::is_interactive() {
+        return Err(anyhow!(
+            "confirmation before submitting is enabled, but there is no terminal to ask; pass --yes to submit anyway"
+        ));
+    }
+    eprintln!("{} {}", style("Problem: ").bold(), problem);
+    eprintln!("{} {}", style("Language:").bold(), language);
+    if let Some(source_name) = &options.source_name {
+        eprintln!("{} {}", style("Source:  ").bold(), source_name);
+    }
+    if !crate::prompt::confirm("Submit?")? {
+        return Err(anyhow!("submission aborted"));
+    }
+    Ok(())
+}
+
+/// Time to wait between consecutive submissions so we don't hit the judge's rate limits
+pub const SUBMISSION_DELAY: Duration = Duration::from_secs(5);
+
+/// Submit each `(name, source, language)` entry to `problem` one after the other and print
+/// their results side by side
+pub fn compare(
+    problem: &str,
+    auth: &Auth,
+    entries: &[(String, String, String)],
+    options: &SubmitOptions,
+) -> Result<()> {
+    let mut results = Vec::new();
+    for (i, (name, source, language)) in entries.iter().enumerate() {
+        if i > 0 {
+            std::thread::sleep(SUBMISSION_DELAY);
+        }
+        println!("{} {}", style("Submitting").bold(), name);
+        results.push(submit(problem, source, auth, language, options)?);
+        println!();
+    }
+
+    let names = entries
+        .iter()
+        .map(|(name, _, _)| name.as_str())
+        .collect::<Vec<_>>();
+    print_comparison(&names, &results, options.memory_unit);
+    Ok(())
+}
+
+/// Submit `source` to `problem` on each of `judges` (base URL and credentials) one after the
+/// other and print their results side by side, e.g. to cross-check a problem that is mirrored.
+/// The results are returned in the order of `judges`.
+pub fn compare_judges(
+    problem: &str,
+    source: &str,
+    language: &str,
+    judges: &[(String, Auth)],
+    options: &SubmitOptions,
+) -> Result<Vec<APISubmission>> {
+    let mut results = Vec::new();
+    for (url, auth) in judges {
+        println!("{} {}", style("Submitting to").bold(), url);
+        let options = SubmitOptions {
+            client: ClientOptions {
+                endpoints: options.client.endpoints.clone().base_url(url),
+                ..options.client.clone()
+            },
+            ..options.clone()
+        };
+        results.push(submit(problem, source, auth, language, &options)?);
+        println!();
+    }
+    let names = judges
+        .iter()
+        .map(|(url, _)| url.as_str())
+        .collect::<Vec<_>>();
+    print_comparison(&names, &results, options.memory_unit);
+    Ok(results)
+}
+
+/// Print the verdicts, scores, peak case times, and memory usage of `results` in columns
+/// headed by `names`
+fn print_comparison(names: &[&str], results: &[APISubmission], memory_unit: MemoryUnit) {
+    let labels = ["Verdict", "Score", "Peak time", "Memory"];
+    let columns = results
+        .iter()
+        .map(|sub| {
+            [
+                sub.result.clone().unwrap_or_else(|| sub.status.clone()),
+                format!("{:.0}/{:.0}", sub.case_points, sub.case_total),
+                sub.max_case_time()
+                    .map(|time| format!("{:.3}s", time))
+                    .unwrap_or_else(|| "---".to_string()),
+                sub.memory
+                    .map(|memory| format_memory(memory, memory_unit))
+                    .unwrap_or_else(|| "---".to_string()),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let width = names
+        .iter()
+        .map(|name| name.len())
+        .max()
+        .unwrap_or(0)
+        .max(12);
+    print!("{:<10}", "");
+    for name in names {
+        print!(" {:<width$}", name, width = width);
+    }
+    println!();
+    for (row, label) in labels.into_iter().enumerate() {
+        print!("{}", style(format!("{:<10}", label)).bold());
+        for column in &columns {
+            print!(" {:<width$}", column[row], width = width);
+        }
+        println!();
+    }
+}
+
+/// Look for a problem whose code matches `problem` case-insensitively
+fn suggest_problem_code(client_options: &ClientOptions, problem: &str) -> Option<String> {
+    search_problems(client_options, problem)
+        .map_err(|err| log::info!("Could not search for similar problems: {:#}", err))
+        .ok()?
+        .into_iter()
+        .map(|summary| summary.code)
+        .find(|code| code.eq_ignore_ascii_case(problem))
+}
+
+/// Save the API response for a submission as pretty-printed JSON to `<output_dir>/<submission_id>.json`
+fn save_response(
+    output_dir: &Path,
+    submission_id: &str,
+    json: &APIResponse<APISingleData<APISubmission>>,
+) -> Result<()> {
+    std::fs::create_dir_all(output_dir)
+        .with_context(|| format!("could not create directory {}", output_dir.display()))?;
+    let path = output_dir.join(format!("{}.json", submission_id));
+    let contents = serde_json::to_string_pretty(json)?;
+    std::fs::write(&path, contents)
+        .with_context(|| format!("could not write {}", path.display()))?;
+    log::info!("Saved submission to {}", path.display());
+    Ok(())
+}
+
+/// Show `text` in the pager from `$PAGER` (`less -R` by default), or print it if stdout isn't
+/// a terminal
+fn page(text: &str) -> Result<()> {
+    use std::io::Write;
+    if !console::Term::stdout().is_term() {
+        print!("{}", text);
+        return Ok(());
+    }
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| "less -R".to_string());
+    let mut command = if cfg!(windows) {
+        let mut command = std::process::Command::new("cmd");
+        command.arg("/C").arg(&pager);
+        command
+    } else {
+        let mut command = std::process::Command::new("sh");
+        command.arg("-c").arg(&pager);
+        command
+    };
+    let mut child = command
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+        .with_context(|| format!("could not run pager `{}`", pager))?;
+    // the pager may exit before reading everything, which is fine
+    let _ = child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(text.as_bytes());
+    child
+        .wait()
+        .with_context(|| format!("could not run pager `{}`", pager))?;
+    Ok(())
+}
+
+/// POST `submission` as JSON to `url`
+fn post_webhook(
+    options: &SubmitOptions,
+    client: &reqwest::blocking::Client,
+    url: &reqwest::Url,
+    submission: &APISubmission,
+) -> Result<()> {
+    let response = options
+        .client
+        .send(client.post(url.clone()).json(submission))?;
+    if !response.status().is_success() {
+        return Err(anyhow!("{} responded with {}", url, response.status()));
+    }
+    log::info!("Sent submission to webhook {}", url);
+    Ok(())
+}
+
+/// Show a desktop notification using the platform's notification tool
+fn notify(title: &str, body: &str) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = std::process::Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {:?} with title {:?}",
+            body, title
+        ));
+        command
+    } else if cfg!(unix) {
+        let mut command = std::process::Command::new("notify-send");
+        command.args(["--app-name", "dmoj-submit", title, body]);
+        command
+    } else {
+        return Err(anyhow!(
+            "desktop notifications are not supported on this platform"
+        ));
+    };
+    let status = command
+        .status()
+        .with_context(|| "could not run notification command")?;
+    if !status.success() {
+        return Err(anyhow!("notification command failed with {}", status));
+    }
+    Ok(())
+}
+
+/// Print the final result of a graded submission
+fn print_summary(submission: &APISubmission, language_name: &str, options: &SubmitOptions) {
+    if let Some(summary_format) = &options.summary_format {
+        println!("{}", summary_format.render(submission, options.memory_unit));
+        return;
+    }
+    println!();
+    println!("{} {}", style("Graded as").bold(), language_name);
+    // https://github.com/DMOJ/online-judge/blob/master/templates/submission/status-testcases.html#L126
+    match submission.result.as_deref() {
+        Some("IE") => {
+            // https://github.com/DMOJ/online-judge/blob/master/templates/submission/internal-error-message.html#L3
+            println!("{}", style("An internal error occurred while grading, and the DMOJ administrators have been notified\nIn the meantime, try resubmitting in a few seconds.").red().bright())
+        }
+        // the API doesn't include the compiler output, but the submission page does
+        Some("CE") => println!(
+            "Compilation error, see {} for the compiler output",
+            options
+                .client
+                .endpoints
+                .submission_page(&submission.id.to_string())
+        ),
+        Some("AB") => println!("Submission aborted!"),
+        _ => {
+            // print resources, which say nothing about output-only submissions
+            // time and memory may be missing even for regular verdicts,
+            // so fall back to `---` rather than panicking
+            if options.problem_type != ProblemType::OutputOnly {
+                println!(
+                    "{} {}, {}",
+                    style("Resources:").bold(),
+                    format_time(submission),
+                    submission
+                        .memory
+                        .map(|memory| format_memory(memory, options.memory_unit))
+                        .unwrap_or_else(|| "---".to_string()),
+                );
+            }
+
+            // TODO: implement maximum single-case runtime
+
+            // print final score
+            print!(
+                "{} {:.0}/{:.0}",
+                style("Final score:").bold(),
+                submission.case_points,
+                submission.case_total
+            );
+            match format_percentage(submission) {
+                Some(percentage) => println!(" ({})", percentage),
+                None => println!(),
+            }
+            if submission.is_pretested {
+                println!(
+                    "{}",
+                    style("Only pretests were run, the full tests may still fail").yellow()
+                );
+            }
+            if options.problem_type == ProblemType::Interactive
+                && submission.result.as_deref() == Some("WA")
+            {
+                println!(
+                    "{}",
+                    style("On interactive problems, WA can also mean the interaction protocol wasn't followed, e.g. output that wasn't flushed").yellow()
+                );
+            }
+        }
+    }
+}
+
+/// Print `value` as pretty JSON, for `--output json`
+fn print_json(value: &impl serde::Serialize) -> Result<()> {
+    let json = serde_json::to_string_pretty(value).with_context(|| "could not serialize output")?;
+    println!("{}", json);
     Ok(())
 }
 
-pub fn list_languages() -> Result<()> {
-    let mut print_lang_list = get_languages()?
+/// Print the details of `problem`, including the languages it can be submitted in
+pub fn problem_info(
+    client_options: &ClientOptions,
+    auth: &Auth,
+    problem: &str,
+    output: OutputFormat,
+) -> Result<()> {
+    let problem = get_problem(client_options, problem, auth)?;
+    if output == OutputFormat::Json {
+        return print_json(&problem);
+    }
+    let mut languages = problem
+        .languages
+        .iter()
+        .map(|key| key.to_lowercase())
+        .collect::<Vec<String>>();
+    languages.sort_unstable();
+    println!("{} ({})", style(&problem.name).bold(), problem.code);
+    println!("{} {}s", style("Time limit:").bold(), problem.time_limit);
+    println!(
+        "{} {}",
+        style("Memory limit:").bold(),
+        format_memory(problem.memory_limit, MemoryUnit::Auto)
+    );
+    println!(
+        "{} {}{}",
+        style("Points:").bold(),
+        problem.points,
+        if problem.partial { " (partial)" } else { "" }
+    );
+    println!(
+        "{} {}",
+        style("Allowed languages:").bold(),
+        languages.join(", ")
+    );
+    Ok(())
+}
+
+/// What a graded submission has to achieve, e.g. to fail CI runs on regressions
+#[derive(Debug, Clone, Default)]
+pub struct Expectations {
+    pub verdict: Option<String>,
+    pub min_score: Option<f64>,
+    /// Longest time a single case may take, in seconds
+    pub max_time: Option<f64>,
+    /// Most memory the submission may use, in MB
+    pub max_memory: Option<f64>,
+}
+
+/// Fail unless `submission` meets all of `expectations`
+pub fn check_outcome(submission: &APISubmission, expectations: &Expectations) -> Result<()> {
+    let actual = submission.result.as_deref().unwrap_or(&submission.status);
+    if let Some(verdict) = &expectations.verdict {
+        if !actual.eq_ignore_ascii_case(verdict) {
+            return Err(anyhow!(
+                "expected {}, got {}",
+                verdict.to_uppercase(),
+                actual
+            ));
+        }
+    }
+    if let Some(min_score) = expectations.min_score {
+        if submission.case_points < min_score {
+            return Err(anyhow!(
+                "expected a score of at least {}, got {:.0}/{:.0}",
+                min_score,
+                submission.case_points,
+                submission.case_total
+            ));
+        }
+    }
+    if let Some(max_time) = expectations.max_time {
+        let peak_time = submission.max_case_time().or(submission.time);
+        if let Some(peak_time) = peak_time.filter(|&time| time > max_time) {
+            return Err(anyhow!(
+                "expected a peak case time of at most {}s, got {:.3}s",
+                max_time,
+                peak_time
+            ));
+        }
+    }
+    if let Some(max_memory) = expectations.max_memory {
+        // the API reports memory in KB
+        let memory = submission.memory.map(|memory| memory / 1024.0);
+        if let Some(memory) = memory.filter(|&memory| memory > max_memory) {
+            return Err(anyhow!(
+                "expected at most {} MB of memory, got {:.2} MB",
+                max_memory,
+                memory
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub fn list_languages(
+    client_options: &ClientOptions,
+    sort_by: LanguageSortKey,
+    output: OutputFormat,
+) -> Result<()> {
+    let mut languages = get_languages(client_options)?;
+    match sort_by {
+        LanguageSortKey::Name => languages.sort_unstable_by_key(|lang| {
+            format!("{}: {}", lang.common_name, lang.key.to_lowercase())
+        }),
+        LanguageSortKey::Key => languages.sort_unstable_by_key(|lang| lang.key.to_lowercase()),
+        LanguageSortKey::Id => languages.sort_unstable_by_key(|lang| lang.id),
+    }
+    if output == OutputFormat::Json {
+        return print_json(&languages);
+    }
+    let print_lang_list = languages
         .into_iter()
         .map(|lang| format!("{}: {}", lang.common_name, lang.key.to_lowercase()))
         .collect::<Vec<String>>();
-    print_lang_list.sort_unstable();
     println!(
         "{}: {}",
         style("Common name").underlined().bold(),
@@ -295,3 +2064,116 @@ pub fn list_languages() -> Result<()> {
     println!("{}", print_lang_list.join("\n"));
     Ok(())
 }
+
+/// Write `language`'s code template to `path`
+pub fn init(
+    client_options: &ClientOptions,
+    language: &str,
+    path: &Path,
+    force: bool,
+) -> Result<()> {
+    if !force && path.exists() {
+        return Err(anyhow!(
+            "{} already exists, pass --force to overwrite it",
+            path.display()
+        ));
+    }
+    let languages = with_retries(LANGUAGES_FETCH_ATTEMPTS, || get_languages(client_options))?;
+    let template = languages
+        .into_iter()
+        .find(|lang| lang.key.eq_ignore_ascii_case(language))
+        .with_context(|| format!("unknown language key {}", language))?
+        .code_template;
+    std::fs::write(path, template)
+        .with_context(|| format!("could not write {}", path.display()))?;
+    println!("Created {}", path.display());
+    Ok(())
+}
+
+/// Print the submissions matching `filters` from the last `args.limit` pages, sorted and
+/// formatted as `args` asks for
+pub fn list_submissions(
+    client_options: &ClientOptions,
+    auth: &Auth,
+    filters: &[(&str, &str)],
+    args: &SubmissionsArgs,
+) -> Result<()> {
+    let limit = args.limit;
+    // submissions are listed oldest first, so the most recent ones are on the last pages
+    let first_page = get_submissions(client_options, auth, filters, 1)?;
+    let oldest_page = (first_page.total_pages - limit + 1).max(1);
+    let mut submissions = Vec::new();
+    for page in oldest_page..=first_page.total_pages {
+        if page == 1 {
+            continue;
+        }
+        submissions.extend(get_submissions(client_options, auth, filters, page)?.objects);
+    }
+    if oldest_page == 1 {
+        submissions.extend(first_page.objects);
+    }
+    // descending order for every key, missing values last
+    let descending = |a: Option<f64>, b: Option<f64>| {
+        b.unwrap_or(f64::NEG_INFINITY)
+            .total_cmp(&a.unwrap_or(f64::NEG_INFINITY))
+    };
+    match args.sort {
+        SubmissionSortKey::Date => submissions.sort_by_key(|sub| std::cmp::Reverse(sub.id)),
+        SubmissionSortKey::Time => submissions.sort_by(|a, b| descending(a.time, b.time)),
+        SubmissionSortKey::Memory => submissions.sort_by(|a, b| descending(a.memory, b.memory)),
+        SubmissionSortKey::Points => submissions.sort_by(|a, b| descending(a.points, b.points)),
+    }
+    if args.output == OutputFormat::Json {
+        return print_json(&submissions);
+    }
+    // one tab-separated line per submission, without styling, for scripts
+    if args.oneline {
+        for sub in submissions {
+            println!(
+                "{}\t{}\t{}\t{}\t{}",
+                sub.id,
+                sub.problem,
+                sub.result.as_deref().unwrap_or_default(),
+                sub.points
+                    .map(|points| format!("{:.0}", points))
+                    .unwrap_or_default(),
+                sub.date
+            );
+        }
+        return Ok(());
+    }
+    if submissions.is_empty() {
+        println!("No submissions found");
+        return Ok(());
+    }
+    println!(
+        "{}",
+        style(format!(
+            "{:<10} {:<14} {:<6} {:>7} {:>8} {:>10} {:<10} {}",
+            "ID", "Problem", "Result", "Points", "Time", "Memory", "Language", "Date"
+        ))
+        .bold()
+        .underlined()
+    );
+    for sub in submissions {
+        println!(
+            "{:<10} {:<14} {:<6} {:>7} {:>8} {:>10} {:<10} {} ({})",
+            sub.id,
+            sub.problem,
+            sub.result.as_deref().unwrap_or("---"),
+            sub.points
+                .map(|points| format!("{:.0}", points))
+                .unwrap_or_else(|| "---".to_string()),
+            sub.time
+                .map(|time| format!("{:.3}s", time))
+                .unwrap_or_else(|| "---".to_string()),
+            sub.memory
+                .map(|memory| format_memory(memory, args.memory_unit))
+                .unwrap_or_else(|| "---".to_string()),
+            sub.language,
+            crate::date::format_local(&sub.date),
+            crate::date::format_relative(&sub.date),
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use console::{Style, StyledObject};
+use std::collections::HashMap;
+
+/// Key for accepted cases that didn't get full points
+pub const PARTIAL_AC: &str = "AC_PARTIAL";
+
+/// Styles of case statuses, e.g. `TLE = "yellow"`. Styles are dotted strings as understood by
+/// `console::Style::from_dotted_str`, e.g. `red.bold` or `white.on_black`.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    styles: HashMap<String, Style>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        let styles = [
+            ("AC", "green"),
+            (PARTIAL_AC, "yellow.bright"),
+            ("WA", "red.bright"),
+            ("TLE", "dim"),
+            ("SC", "dim"),
+            ("MLE", "red"),
+            ("OLE", "red"),
+            ("RTE", "red"),
+            ("IR", "red"),
+            ("CE", "yellow"),
+        ]
+        .into_iter()
+        .map(|(status, style)| (status.to_string(), Style::from_dotted_str(style)))
+        .collect();
+        Self { styles }
+    }
+}
+
+impl Theme {
+    /// The default theme with the styles in `overrides` applied
+    pub fn with_overrides(overrides: &HashMap<String, String>) -> Self {
+        let mut theme = Self::default();
+        for (status, style) in overrides {
+            theme
+                .styles
+                .insert(status.to_uppercase(), Style::from_dotted_str(style));
+        }
+        theme
+    }
+
+    /// Apply the style of `status` to `text`. Unknown statuses are shown in bold.
+    pub fn apply<D>(&self, status: &str, text: D) -> StyledObject<D> {
+        match self.styles.get(status) {
+            Some(style) => style.apply_to(text),
+            None => Style::new().bold().apply_to(text),
+        }
+    }
+
+    pub fn is_known(&self, status: &str) -> bool {
+        self.styles.contains_key(status)
+    }
+}
@@ -0,0 +1,51 @@
+use console::{style, Term};
+use std::io;
+use std::time::Instant;
+
+/// Full-screen view of a submission being graded, redrawn on every poll
+pub struct LiveView {
+    term: Term,
+    title: String,
+    started: Instant,
+}
+
+impl LiveView {
+    pub fn new(title: String) -> Self {
+        let term = Term::stdout();
+        // best effort, the view still works with a visible cursor
+        let _ = term.hide_cursor();
+        Self {
+            term,
+            title,
+            started: Instant::now(),
+        }
+    }
+
+    /// Redraw the screen with `lines` in the middle and `status` in the footer. Only the last
+    /// lines are shown if they don't fit on the screen.
+    pub fn draw(&self, lines: &[String], status: &str) -> io::Result<()> {
+        let (rows, _) = self.term.size();
+        // title, blank line, blank line, footer
+        let available = (rows as usize).saturating_sub(4).max(1);
+        let visible = &lines[lines.len().saturating_sub(available)..];
+        self.term.clear_screen()?;
+        self.term
+            .write_line(&style(&self.title).bold().underlined().to_string())?;
+        self.term.write_line("")?;
+        for line in visible {
+            self.term.write_line(line)?;
+        }
+        self.term.write_line("")?;
+        self.term.write_line(&format!(
+            "{}  {}",
+            style(status).bold(),
+            style(format!("{}s elapsed", self.started.elapsed().as_secs())).dim()
+        ))
+    }
+}
+
+impl Drop for LiveView {
+    fn drop(&mut self) {
+        let _ = self.term.show_cursor();
+    }
+}
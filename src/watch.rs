@@ -0,0 +1,58 @@
+use crate::config::ConfyConfig;
+use crate::resolve::source_files;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often the directory is checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long a file must stay unchanged before it is submitted
+const SETTLE_TIME: Duration = POLL_INTERVAL;
+
+fn snapshot(dir: &Path, cfg: &ConfyConfig) -> Result<HashMap<PathBuf, SystemTime>> {
+    Ok(source_files(dir, cfg)?.into_iter().collect())
+}
+
+/// Call `on_change` with every source file in `dir` that is saved, until the process is
+/// interrupted. Changes are only reported once the file has stopped changing, since editors
+/// often write a file in several steps.
+pub fn watch(
+    dir: &Path,
+    cfg: &ConfyConfig,
+    mut on_change: impl FnMut(&Path) -> Result<()>,
+) -> Result<()> {
+    let mut seen = snapshot(dir, cfg)?;
+    // files that changed -> when to submit them if they don't change again
+    let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let current = snapshot(dir, cfg)?;
+        let now = Instant::now();
+        for (path, modified) in &current {
+            if seen.get(path) != Some(modified) {
+                // still being written, wait for it to settle
+                pending.insert(path.clone(), now + SETTLE_TIME);
+            }
+        }
+        seen = current;
+        let mut due = pending
+            .iter()
+            .filter(|(_, deadline)| **deadline <= now)
+            .map(|(path, deadline)| (*deadline, path.clone()))
+            .collect::<Vec<_>>();
+        if due.is_empty() {
+            continue;
+        }
+        // in the order they were saved
+        due.sort();
+        for (_, path) in due {
+            pending.remove(&path);
+            if let Err(err) = on_change(&path) {
+                log::error!("Submitting {} failed: {:#}", path.display(), err);
+            }
+        }
+        println!("Watching for changes, press Ctrl-C to stop");
+    }
+}